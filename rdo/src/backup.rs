@@ -0,0 +1,285 @@
+use std::process::{Command, Stdio};
+
+use crate::cli::BackupArgs;
+use crate::dump::{Compression, DumpFormat, DUMP_CANDIDATES};
+use crate::error::RdoError;
+use crate::output::{self, collect, join};
+use crate::profile::Profile;
+use crate::sql::conninfo_dbname;
+
+/// Orden de shell que escribe la entrada estándar en la ruta recibida como primer argumento
+const WRITE_SCRIPT: &str = "cat > \"$1\"";
+
+impl Profile {
+    // Crear un backup con pg_dump en la carpeta que espera `rdo restore`
+    pub fn create_backup(&self, namedb: &str, opts: &BackupArgs) -> Result<(), RdoError> {
+        let container_id = self.container().ok_or("Falta ID del contenedor")?;
+
+        if opts.compress != Compression::None && matches!(opts.format, DumpFormat::Custom | DumpFormat::Directory) {
+            return Err("--compress solo se aplica a los formatos plain y tar; custom y directory ya los comprime pg_dump".into());
+        }
+        if opts.jobs.is_some() && opts.format != DumpFormat::Directory {
+            return Err("--jobs solo se admite con --format directory".into());
+        }
+        if !self.database_exists(namedb)? {
+            return Err(RdoError::MissingDatabase(format!("La base de datos '{}' no existe", namedb)));
+        }
+
+        // Con plantilla de ruta, el dump va donde ella diga, con la fecha de ahora y la extensión
+        // del formato; sin ella, a la carpeta del backup. Los backups de Odoo se llaman
+        // <db>_<fecha>.zip, como los del gestor de bases de datos
+        let now = chrono::Local::now();
+        let template = self.path_template()?;
+        let path = match &template {
+            Some(template) => {
+                let ext = match opts.zip {
+                    true => "zip".to_string(),
+                    false => format!("{}{}", opts.format.extension(), opts.compress.extension()),
+                };
+                let dir_backup = self.dir_backup.as_deref().ok_or("Falta directorio de backup")?;
+                template.render(dir_backup, namedb, now.naive_local(), &ext)?
+            }
+            None if opts.zip => format!("{}/{}_{}.zip", self.backup_folder(namedb)?, namedb, now.format("%Y-%m-%d_%H-%M-%S")),
+            None => format!("{}/{}{}", self.backup_folder(namedb)?, opts.format.file_name(), opts.compress.extension()),
+        };
+        let (folder, file_name) = path.rsplit_once('/').ok_or("Ruta de backup inválida")?;
+
+        // Dumps que ya hay en la carpeta; solo se reemplazan con --force. Con plantilla,
+        // solo cuenta uno con la misma ruta
+        let entries = self.container_output(container_id, &["ls", "-1Ap", folder]).unwrap_or_default();
+        let existing: Vec<&str> = entries.lines()
+            .map(|e| e.trim_end_matches('/'))
+            .filter(|e| match template {
+                Some(_) => *e == file_name,
+                None => DUMP_CANDIDATES.contains(e) || e.ends_with(".zip"),
+            })
+            .collect();
+        if !existing.is_empty() && !opts.force {
+            return Err(format!(
+                "Ya hay un dump en {} ({}). Use --force para reemplazarlo",
+                folder, existing.join(", ")
+            ).into());
+        }
+
+        // Se escribe en un archivo temporal de la misma carpeta para no perder el
+        // backup anterior si pg_dump falla
+        let partial = format!("{}/.{}.partial", folder, file_name);
+        self.container_output(container_id, &["mkdir", "-p", folder])
+            .map_err(|e| format!("No se pudo crear la carpeta de backup {}: {}", folder, e))?;
+
+        if opts.zip {
+            println!("Creando backup ZIP de Odoo de '{}' en {}", namedb, path);
+        } else {
+            println!("Creando backup de '{}' en {} (formato {}, {})", namedb, path, opts.format, opts.compress);
+        }
+
+        let result = if opts.zip {
+            self.create_odoo_zip(container_id, namedb, &partial)
+        } else if opts.compress == Compression::None {
+            self.pg_dump_to_file(container_id, namedb, opts, &partial)
+        } else {
+            self.pg_dump_compressed(container_id, namedb, opts, &partial)
+        };
+        if let Err(e) = result {
+            let _ = self.container_output(container_id, &["rm", "-rf", &partial]);
+            return Err(e);
+        }
+
+        for name in &existing {
+            println!("Eliminando el dump anterior {}/{}", folder, name);
+            self.container_output(container_id, &["rm", "-rf", &format!("{}/{}", folder, name)])?;
+        }
+        self.container_output(container_id, &["mv", &partial, &path])?;
+
+        println!("Backup creado: {}", path);
+        Ok(())
+    }
+
+    // Argumentos de pg_dump. Sin `path`, el dump se escribe en la salida estándar
+    pub fn dump_args(&self, format: DumpFormat, path: Option<&str>, namedb: &str, jobs: Option<u32>, no_owner: bool) -> Result<Vec<String>, RdoError> {
+        let (Some(xhost), Some(port), Some(username)) = (&self.xhost, self.port, &self.username) else {
+            return Err(RdoError::Config("Faltan datos del perfil".into()));
+        };
+
+        let mut args = vec![
+            "pg_dump".to_string(),
+            "--host".to_string(), xhost.to_string(),
+            "--port".to_string(), port.to_string(),
+            "--username".to_string(), username.to_string(),
+            "--format".to_string(), format.pg_dump_flag().to_string(),
+        ];
+        if let Some(jobs) = jobs {
+            args.extend(["--jobs".to_string(), jobs.to_string()]);
+        }
+        if no_owner {
+            args.push("--no-owner".to_string());
+        }
+        if let Some(path) = path {
+            args.extend(["--file".to_string(), path.to_string()]);
+        }
+        // Con --dbname, un nombre que empiece por '-' no se toma como opción, y como cadena de
+        // conexión uno con '=' tampoco se toma como host u otros parámetros
+        args.extend(["--dbname".to_string(), conninfo_dbname(namedb)]);
+        Ok(args)
+    }
+
+    // pg_dump escribe el archivo directamente dentro del contenedor
+    fn pg_dump_to_file(&self, container_id: &str, namedb: &str, opts: &BackupArgs, path: &str) -> Result<(), RdoError> {
+        let args = self.dump_args(opts.format, Some(path), namedb, opts.jobs, opts.no_owner)?;
+        println!("Ejecutando en {}:", self.describe(container_id));
+        println!("{}", args.join(" "));
+
+        let output = output::run(&mut self.pg_command(container_id, &args, false))?;
+        if !output.status.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(())
+    }
+
+    // La salida de pg_dump se comprime en el host y vuelve al contenedor por la entrada estándar
+    fn pg_dump_compressed(&self, container_id: &str, namedb: &str, opts: &BackupArgs, path: &str) -> Result<(), RdoError> {
+        let args = self.dump_args(opts.format, None, namedb, None, opts.no_owner)?;
+        println!("Ejecutando en {} (comprimido con {} en el host):", self.describe(container_id), opts.compress);
+        println!("{}", args.join(" "));
+
+        let mut dump = self.pg_command(container_id, &args, false)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let dump_stderr = collect(dump.stderr.take());
+
+        let mut writer = self.container_writer(container_id, path)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let writer_stderr = collect(writer.stderr.take());
+
+        let input = dump.stdout.take().ok_or("No se pudo leer la salida de pg_dump")?;
+        let output = writer.stdin.take().ok_or("No se pudo abrir el archivo del backup")?;
+        let copied = opts.compress.encode(input, output);
+
+        let dump_status = dump.wait()?;
+        let writer_status = writer.wait()?;
+        if !dump_status.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&join(dump_stderr))));
+        }
+        if !writer_status.success() {
+            return Err(RdoError::classify(&format!("Error al escribir {}: {}", path, String::from_utf8_lossy(&join(writer_stderr)))));
+        }
+        copied.map_err(|e| format!("Error al comprimir el dump: {}", e))?;
+        Ok(())
+    }
+
+    // Orden que guarda su entrada estándar en una ruta del contenedor
+    pub fn container_writer(&self, container_id: &str, path: &str) -> Command {
+        self.exec(container_id, &["sh", "-c", WRITE_SCRIPT, "rdo", path], true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::FakeRuntime;
+    use std::sync::Arc;
+
+    // Perfil que ejecuta las órdenes con un ejecutor falso
+    fn profile(fake: &Arc<FakeRuntime>) -> Profile {
+        Profile {
+            xhost: Some("db".into()),
+            port: Some(5432),
+            username: Some("odoo".into()),
+            container_id: Some("ctr".into()),
+            dir_backup: Some("/backups".into()),
+            executor: Some(fake.clone()),
+            ..Profile::default()
+        }
+    }
+
+    fn args(format: DumpFormat, compress: Compression) -> BackupArgs {
+        BackupArgs { format, compress, jobs: None, no_owner: false, zip: false, force: false }
+    }
+
+    // Contenedor con la base de datos y, en la carpeta del backup, lo que lista `ls`;
+    // pg_dump termina con `dump_stderr` como error si no está vacío
+    fn container(ls: &'static str, dump_stderr: &'static str) -> Arc<FakeRuntime> {
+        FakeRuntime::new(move |argv| match argv[0] {
+            "psql" => (0, "1\n".into(), String::new()),
+            "ls" => (0, ls.into(), String::new()),
+            "pg_dump" if !dump_stderr.is_empty() => (1, String::new(), dump_stderr.into()),
+            "pg_dump" | "mkdir" | "rm" | "mv" => (0, String::new(), String::new()),
+            _ => (2, String::new(), "orden inesperada".into()),
+        })
+    }
+
+    // Órdenes ejecutadas, sin el contenedor
+    fn commands(fake: &FakeRuntime) -> Vec<Vec<String>> {
+        fake.calls().into_iter().map(|call| call[1..].to_vec()).collect()
+    }
+
+    #[test]
+    fn existing_dumps_are_only_replaced_with_force() {
+        let fake = container("dump.sql\nnotas.txt\n", "");
+        let p = profile(&fake);
+        let err = p.create_backup("prod", &args(DumpFormat::Plain, Compression::None)).unwrap_err();
+        assert!(err.to_string().contains("Ya hay un dump en /backups/prod (dump.sql)"), "{}", err);
+        // Se rechaza antes de crear nada
+        assert_eq!(commands(&fake).iter().map(|c| c[0].as_str()).collect::<Vec<_>>(), ["psql", "ls"]);
+
+        let fake = container("dump.sql\nnotas.txt\n", "");
+        let p = profile(&fake);
+        p.create_backup("prod", &BackupArgs { force: true, ..args(DumpFormat::Plain, Compression::None) }).unwrap();
+        let commands = commands(&fake);
+        assert_eq!(commands[2], ["mkdir", "-p", "/backups/prod"]);
+        assert_eq!(commands[3][0], "pg_dump");
+        assert!(commands[3].windows(2).any(|w| w == ["--file", "/backups/prod/.dump.sql.partial"]), "{:?}", commands[3]);
+        // El anterior solo se borra cuando el nuevo ya está escrito, y el parcial ocupa su lugar
+        assert_eq!(commands[4], ["rm", "-rf", "/backups/prod/dump.sql"]);
+        assert_eq!(commands[5], ["mv", "/backups/prod/.dump.sql.partial", "/backups/prod/dump.sql"]);
+        assert_eq!(commands.len(), 6);
+    }
+
+    #[test]
+    fn failed_dumps_remove_the_partial_file_and_keep_the_previous_one() {
+        let fake = container("dump.sql\n", "pg_dump: error: ERROR:  53100: could not extend file: No space left on device\n");
+        let p = profile(&fake);
+        let result = p.create_backup("prod", &BackupArgs { force: true, ..args(DumpFormat::Plain, Compression::None) });
+        assert!(matches!(result, Err(RdoError::DiskFull(_))), "{:?}", result);
+
+        let commands = commands(&fake);
+        assert_eq!(commands.last().unwrap(), &["rm", "-rf", "/backups/prod/.dump.sql.partial"]);
+        assert!(!commands.iter().any(|c| c[0] == "mv" || c.last().unwrap() == "/backups/prod/dump.sql"), "{:?}", commands);
+    }
+
+    #[test]
+    fn compression_and_jobs_are_checked_before_running_anything() {
+        let rejected = [
+            args(DumpFormat::Custom, Compression::Gzip),
+            args(DumpFormat::Directory, Compression::Zstd),
+            BackupArgs { jobs: Some(4), ..args(DumpFormat::Plain, Compression::None) },
+            BackupArgs { jobs: Some(4), ..args(DumpFormat::Custom, Compression::None) },
+        ];
+        for opts in rejected {
+            let fake = container("", "");
+            assert!(profile(&fake).create_backup("prod", &opts).is_err(), "{:?}", opts);
+            assert!(fake.calls().is_empty(), "{:?}", opts);
+        }
+    }
+
+    #[test]
+    fn dump_args_match_the_format() {
+        let fake = container("", "");
+        let p = profile(&fake);
+        for format in [DumpFormat::Plain, DumpFormat::Custom, DumpFormat::Directory, DumpFormat::Tar] {
+            let args = p.dump_args(format, Some("/backups/prod/dump"), "prod", None, false).unwrap();
+            assert_eq!(args, [
+                "pg_dump", "--host", "db", "--port", "5432", "--username", "odoo",
+                "--format", format.pg_dump_flag(), "--file", "/backups/prod/dump", "--dbname", "dbname='prod'",
+            ]);
+        }
+
+        // Sin ruta, el dump va a la salida estándar
+        let args = p.dump_args(DumpFormat::Directory, None, "prod", Some(4), true).unwrap();
+        assert_eq!(args[7..], ["--format", "d", "--jobs", "4", "--no-owner", "--dbname", "dbname='prod'"]);
+        assert!(fake.calls().is_empty());
+    }
+}
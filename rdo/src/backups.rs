@@ -0,0 +1,799 @@
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime};
+use std::collections::BTreeMap;
+use indicatif::HumanBytes;
+use serde::Serialize;
+
+use crate::cli::{BackupSort, ListArgs};
+use crate::db::check_db_name;
+use crate::dump::{choose_dump, CandidateKind, Compression, Dump, DumpFormat, Location, TAR_MAGIC_OFFSET};
+use crate::error::RdoError;
+use crate::odoo::Manifest;
+use crate::profile::Profile;
+use crate::template::{date_in_name, glob_match, Captures, PathTemplate};
+
+/// Ficha de un backup para `rdo backups list`
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct BackupInfo {
+    /// Nombre de la carpeta (y de la base de datos); con plantilla de ruta, el valor de {name}
+    pub name: String,
+    /// Ruta de la carpeta
+    pub path: String,
+    /// Nombre del dump dentro de la carpeta; ninguno si la carpeta no tiene dump
+    pub dump: Option<String>,
+    /// Fecha del backup, de {date} en la ruta o escrita en el nombre: 2025-04-28 o 2025-04-28T17:48:40
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// La misma fecha, para ordenar
+    #[serde(skip)]
+    pub date_key: Option<NaiveDateTime>,
+    pub format: Option<DumpFormat>,
+    pub compression: Option<Compression>,
+    /// Tamaño en bytes (en formato directorio, la suma de sus archivos)
+    pub size: Option<u64>,
+    /// Fecha de modificación en RFC 3339
+    pub modified: Option<String>,
+    /// Fecha de modificación en segundos, para ordenar
+    #[serde(skip)]
+    pub mtime: Option<i64>,
+    /// Solo con --checksum y dumps de un único archivo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Versión de Odoo del manifest.json
+    pub odoo_version: Option<String>,
+    /// Base de datos original del manifest.json
+    pub db_name: Option<String>,
+}
+
+impl BackupInfo {
+    // Tamaño legible, como "2.98 KiB"
+    pub fn size_label(&self) -> String {
+        self.size.map(|s| HumanBytes(s).to_string()).unwrap_or_else(|| "-".to_string())
+    }
+
+    // Fecha de modificación en la hora local
+    pub fn date_label(&self) -> String {
+        self.mtime.and_then(|t| DateTime::from_timestamp(t, 0))
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    // Ruta completa del dump, si la carpeta tiene uno
+    pub fn dump_path(&self) -> Option<String> {
+        self.dump.as_ref().map(|dump| format!("{}/{}", self.path, dump))
+    }
+
+    // Guardar la fecha del backup; a medianoche se muestra sin hora
+    fn set_date(&mut self, date: Option<NaiveDateTime>) {
+        self.date = date.map(|d| match d.time() == NaiveTime::MIN {
+            true => d.format("%Y-%m-%d").to_string(),
+            false => d.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        });
+        self.date_key = date;
+    }
+
+    // Clave de antigüedad: la fecha del nombre o de la plantilla y después la de modificación.
+    // Un backup con fecha es siempre más reciente que uno sin ella
+    fn recency(&self) -> (Option<NaiveDateTime>, Option<i64>) {
+        (self.date_key, self.mtime)
+    }
+
+    pub fn compression_label(&self) -> String {
+        match self.compression {
+            None | Some(Compression::None) => "-".to_string(),
+            Some(Compression::Zip) => "zip".to_string(),
+            Some(c) => c.to_string(),
+        }
+    }
+}
+
+impl Profile {
+    // Listar los backups con los datos de su dump, ordenados y filtrados según `opts`
+    pub fn view_backup_folders(&self, opts: &ListArgs) -> Result<(), RdoError> {
+        let (Some(container_id), Some(dir_backup)) = (self.container(), &self.dir_backup) else {
+            return if self.container_id.is_none() {
+                Err("Falta ID del contenedor. Especifique --container_id".into())
+            } else {
+                Err("Falta directorio de backup. Especifique --dir_backup".into())
+            };
+        };
+
+        let backups = select(self.backup_catalog(opts.checksum)?, opts);
+        if opts.json {
+            println!("{}", serde_json::to_string_pretty(&backups)?);
+            return Ok(());
+        }
+
+        let template = self.path_template()?;
+        match &template {
+            Some(template) => println!("Backups según la plantilla {} (dentro de {}):", template, self.describe(container_id)),
+            None => println!("Backups en {} (dentro de {}):", dir_backup, self.describe(container_id)),
+        }
+        if backups.is_empty() {
+            match template {
+                Some(_) => println!("No se encontraron dumps que coincidan con la plantilla."),
+                None => println!("No se encontraron carpetas de backup."),
+            }
+            return Ok(());
+        }
+        print_table(&backups, opts.checksum);
+        Ok(())
+    }
+
+    // Ficha de cada carpeta de backup, por orden de nombre. Todo se recoge con una sola
+    // orden en el contenedor, así que el tiempo no depende del número de carpetas
+    pub fn backup_catalog(&self, checksum: bool) -> Result<Vec<BackupInfo>, RdoError> {
+        if let Some(template) = self.path_template()? {
+            return self.template_catalog(&template, None, checksum);
+        }
+        let container_id = self.container().ok_or("Falta ID del contenedor")?;
+        let dir_backup = self.dir_backup.as_deref().ok_or("Falta directorio de backup")?;
+
+        let checksum = if checksum { "1" } else { "0" };
+        let listing = self.container_bytes(container_id, &["sh", "-c", LIST_SCRIPT, "rdo-list", dir_backup, checksum])?;
+
+        let mut backups: Vec<BackupInfo> = parse_listing(&listing)?.iter().map(FolderListing::describe).collect();
+        backups.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(backups)
+    }
+
+    // Ficha de cada dump que coincide con la plantilla de ruta, por nombre y fecha; con `name`,
+    // solo los de ese backup. También es una sola orden: `find` baja solo a la profundidad
+    // de la plantilla y el host descarta lo que no coincide del todo
+    fn template_catalog(&self, template: &PathTemplate, name: Option<&str>, checksum: bool) -> Result<Vec<BackupInfo>, RdoError> {
+        let container_id = self.container().ok_or("Falta ID del contenedor")?;
+        let dir_backup = self.dir_backup.as_deref().ok_or("Falta directorio de backup")?;
+
+        let search = template.search(dir_backup, name)?;
+        let depth = search.depth.to_string();
+        let checksum = if checksum { "1" } else { "0" };
+        let listing = self.container_bytes(container_id, &[
+            "sh", "-c", FIND_SCRIPT, "rdo-find", &search.root, &depth, &search.pattern, checksum, ENTRY_SCRIPT,
+        ])?;
+
+        let mut backups: Vec<BackupInfo> = parse_entries(&listing)?.iter()
+            .filter(|(path, _)| !is_auxiliary(path))
+            .filter_map(|(path, entry)| Some(entry.describe(path, template.matches(dir_backup, path)?)))
+            .filter(|b| name.is_none_or(|name| b.name == name))
+            .collect();
+        backups.sort_by(|a, b| (&a.name, a.date_key, &a.path, &a.dump).cmp(&(&b.name, b.date_key, &b.path, &b.dump)));
+        Ok(backups)
+    }
+
+    // Dump de un backup según la plantilla de ruta: `backup` es un nombre, y se usa el más
+    // reciente de sus dumps, o la ruta de uno de ellos
+    pub fn locate_template_dump(&self, template: &PathTemplate, backup: &str) -> Result<Dump, RdoError> {
+        let dir_backup = self.dir_backup.as_deref().ok_or("Falta directorio de backup")?;
+        let (name, path) = if backup.contains('/') {
+            let captures = template.matches(dir_backup, backup).ok_or_else(|| RdoError::MissingDump(format!(
+                "{} no coincide con la plantilla de ruta {}", backup, template
+            )))?;
+            (captures.name, Some(backup))
+        } else {
+            check_db_name(backup)?;
+            (backup.to_string(), None)
+        };
+
+        let found: Vec<BackupInfo> = self.template_catalog(template, Some(&name), false)
+            .map_err(|e| match e {
+                RdoError::Other(m) => RdoError::MissingDump(format!("No se pudieron buscar los dumps de '{}': {}", name, m)),
+                e => e,
+            })?
+            .into_iter()
+            .filter(|b| path.is_none_or(|path| b.dump_path().as_deref() == Some(path)))
+            .collect();
+
+        let Some(newest) = newest(&found) else {
+            return Err(RdoError::MissingDump(format!(
+                "No se encontró ningún dump de '{}' con la plantilla de ruta {}", name, template
+            )));
+        };
+        if found.len() > 1 {
+            println!("Hay {} dumps de '{}'; se usa el más reciente: {}", found.len(), name, newest.dump_path().unwrap_or_default());
+        }
+        Ok(newest.to_dump())
+    }
+
+    // Backup más reciente cuyo nombre empieza por `pattern` o, si tiene * o ?, coincide con él.
+    // Se indica cuál se eligió y por qué; devuelve lo que se pasa a `execute_psql`: el nombre
+    // o, con plantilla de ruta, la ruta exacta del dump
+    pub fn latest_backup(&self, pattern: &str) -> Result<String, RdoError> {
+        let matching = matching_backups(self.backup_catalog(false)?, pattern);
+        let Some(latest) = newest(&matching) else {
+            return Err(RdoError::MissingDump(format!("Ningún backup con dump coincide con '{}'", pattern)));
+        };
+
+        let reason = match &latest.date {
+            Some(date) => format!("fecha {}", date),
+            None => format!("sin fecha en el nombre; modificado el {}", latest.date_label()),
+        };
+        let among = match matching.len() {
+            1 => format!("el único que coincide con '{}'", pattern),
+            n => format!("el más reciente de los {} que coinciden con '{}'", n, pattern),
+        };
+        println!("Backup elegido: {} ({}), {}", latest.name, reason, among);
+        Ok(match &self.path_template {
+            Some(_) => latest.dump_path().unwrap_or_else(|| latest.name.clone()),
+            None => latest.name.clone(),
+        })
+    }
+}
+
+// Backups con dump cuyo nombre empieza por `pattern` o, si tiene * o ?, coincide con él
+fn matching_backups(backups: Vec<BackupInfo>, pattern: &str) -> Vec<BackupInfo> {
+    let glob = pattern.contains(['*', '?']);
+    backups.into_iter()
+        .filter(|b| b.dump.is_some())
+        .filter(|b| if glob { glob_match(pattern, &b.name) } else { b.name.starts_with(pattern) })
+        .collect()
+}
+
+// El más reciente: por la fecha de la ruta o del nombre y, a igual fecha o sin ella, por la
+// de modificación. Un backup con fecha gana siempre a uno sin ella
+fn newest(backups: &[BackupInfo]) -> Option<&BackupInfo> {
+    backups.iter().max_by(|a, b| a.recency().cmp(&b.recency()).then_with(|| a.path.cmp(&b.path)))
+}
+
+// Recorre las carpetas de "$1" y escribe registros de campos terminados en NUL, que
+// no pueden aparecer en un nombre de archivo:
+//   D carpeta                  empieza una carpeta
+//   F nombre bytes segundos    archivo
+//   d nombre                   subdirectorio
+//   T nombre estadísticas      subdirectorio con toc.dat; "bytes segundos" por archivo y línea
+//   H nombre hex hex           primeros 8 bytes y bytes 257-261 (marca de tar) de dump* y *.zip
+//   C nombre sha256            con "$2" = 1, suma de dump* y *.zip
+//   Z nombre json              manifest.json dentro de un zip (si hay unzip)
+//   M json                     manifest.json de la carpeta
+const LIST_SCRIPT: &str = r#"[ -d "$1" ] || { echo "No existe el directorio de backups $1" >&2; exit 1; }
+for d in "$1"/*/ "$1"/.[!.]*/ "$1"/..?*/; do
+  [ -d "$d" ] || continue
+  d=${d%/}
+  printf 'D\0%s\0' "$d"
+  for e in "$d"/* "$d"/.[!.]* "$d"/..?*; do
+    [ -e "$e" ] || continue
+    n=${e##*/}
+    if [ -d "$e" ]; then
+      if [ -f "$e/toc.dat" ]; then
+        printf 'T\0%s\0' "$n"
+        find "$e" -type f -exec stat -c '%s %Y' {} +
+        printf '\0'
+      else
+        printf 'd\0%s\0' "$n"
+      fi
+      continue
+    fi
+    printf 'F\0%s\0%s\0' "$n" "$(stat -c '%s %Y' "$e")"
+    case "$n" in
+      dump*|*.zip)
+        printf 'H\0%s\0%s\0%s\0' "$n" "$(od -An -tx1 -N8 "$e")" "$(od -An -tx1 -j257 -N5 "$e" 2>/dev/null)"
+        if [ "$2" = 1 ]; then printf 'C\0%s\0%s\0' "$n" "$(sha256sum < "$e")"; fi
+        ;;
+    esac
+    case "$n" in
+      *.zip) if m=$(unzip -p "$e" manifest.json 2>/dev/null); then printf 'Z\0%s\0%s\0' "$n" "$m"; fi ;;
+      manifest.json) printf 'M\0%s\0' "$(cat "$e")" ;;
+    esac
+  done
+done
+"#;
+
+/// Lo que el script cuenta de una carpeta de backup
+#[derive(Debug, Default)]
+struct FolderListing {
+    path: String,
+    /// Entradas como en `ls -1Ap`: los directorios con '/' al final
+    entries: Vec<String>,
+    /// Tamaño y fecha de cada archivo
+    stats: BTreeMap<String, (u64, Option<i64>)>,
+    /// Directorios con toc.dat: suma de tamaños y fecha más reciente
+    directory_dumps: BTreeMap<String, (u64, Option<i64>)>,
+    headers: BTreeMap<String, Vec<u8>>,
+    sums: BTreeMap<String, String>,
+    zip_manifests: BTreeMap<String, String>,
+    manifest: Option<String>,
+}
+
+// Con plantilla de ruta: busca en "$1", a profundidad "$2", lo que coincide con el patrón "$3"
+// y pasa cada resultado a ENTRY_SCRIPT ("$5"), que recibe "$4" para las sumas. El script va
+// en una variable porque `find` no admite otro {} en los argumentos de -exec ... +
+const FIND_SCRIPT: &str = r#"[ -d "$1" ] || { echo "No existe el directorio $1" >&2; exit 1; }
+RDO_ENTRY=$5 find "$1" -mindepth "$2" -maxdepth "$2" -path "$3" \( -type f -o -type d \) \
+  -exec sh -c 'eval "$RDO_ENTRY"' rdo-entry "$4" {} +
+"#;
+
+// Registros de cada ruta encontrada, como los de LIST_SCRIPT pero con la ruta completa:
+//   F ruta bytes segundos · T ruta estadísticas · H ruta hex hex · C ruta sha256
+//   Z ruta json · M ruta json (manifest.json junto al dump)
+// Los directorios solo cuentan si tienen toc.dat
+const ENTRY_SCRIPT: &str = r#"sums=$1; shift
+for e; do
+  if [ -d "$e" ]; then
+    [ -f "$e/toc.dat" ] || continue
+    printf 'T\0%s\0' "$e"
+    find "$e" -type f -exec stat -c '%s %Y' {} +
+    printf '\0'
+  else
+    printf 'F\0%s\0%s\0' "$e" "$(stat -c '%s %Y' "$e")"
+    printf 'H\0%s\0%s\0%s\0' "$e" "$(od -An -tx1 -N8 "$e")" "$(od -An -tx1 -j257 -N5 "$e" 2>/dev/null)"
+    if [ "$sums" = 1 ]; then printf 'C\0%s\0%s\0' "$e" "$(sha256sum < "$e")"; fi
+    case "$e" in
+      *.zip) if m=$(unzip -p "$e" manifest.json 2>/dev/null); then printf 'Z\0%s\0%s\0' "$e" "$m"; fi ;;
+    esac
+  fi
+  if [ -f "${e%/*}/manifest.json" ]; then printf 'M\0%s\0%s\0' "$e" "$(cat "${e%/*}/manifest.json")"; fi
+done
+"#;
+
+/// Lo que ENTRY_SCRIPT cuenta de una ruta
+#[derive(Debug, Default)]
+struct EntryListing {
+    /// Dump en formato directorio (con toc.dat)
+    directory: bool,
+    /// Tamaño y fecha; en formato directorio, la suma y el más reciente de sus archivos
+    stats: (u64, Option<i64>),
+    header: Vec<u8>,
+    sum: Option<String>,
+    zip_manifest: Option<String>,
+    manifest: Option<String>,
+}
+
+// Separar la salida de ENTRY_SCRIPT por rutas
+fn parse_entries(listing: &[u8]) -> Result<BTreeMap<String, EntryListing>, RdoError> {
+    let mut fields = listing.split(|b| *b == 0).map(|f| String::from_utf8_lossy(f).into_owned());
+    let mut entries: BTreeMap<String, EntryListing> = BTreeMap::new();
+    let invalid = |tag: &str| RdoError::Other(format!("Salida inesperada al buscar los dumps (registro '{}')", tag));
+
+    while let Some(tag) = fields.next() {
+        if tag.is_empty() {
+            continue;
+        }
+        let mut next = || fields.next().ok_or_else(|| invalid(&tag));
+        let entry = entries.entry(next()?).or_default();
+        match tag.as_str() {
+            "F" => entry.stats = parse_stats(&next()?),
+            "T" => {
+                entry.directory = true;
+                entry.stats = parse_stats(&next()?);
+            }
+            "H" => {
+                let (start, tar) = (parse_hex(&next()?), parse_hex(&next()?));
+                entry.header = header_from(start, tar);
+            }
+            "C" => entry.sum = next()?.split_whitespace().next().map(str::to_string),
+            "Z" => entry.zip_manifest = Some(next()?),
+            "M" => entry.manifest = Some(next()?),
+            _ => return Err(invalid(&tag)),
+        }
+    }
+    Ok(entries)
+}
+
+// Archivos que una plantilla con comodines puede encontrar pero no son dumps:
+// el manifest.json de la carpeta y los backups a medio escribir
+fn is_auxiliary(path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    file == "manifest.json" || (file.starts_with('.') && file.ends_with(".partial"))
+}
+
+// Separar la salida de LIST_SCRIPT en carpetas
+fn parse_listing(listing: &[u8]) -> Result<Vec<FolderListing>, RdoError> {
+    let mut fields = listing.split(|b| *b == 0).map(|f| String::from_utf8_lossy(f).into_owned());
+    let mut folders: Vec<FolderListing> = Vec::new();
+    let invalid = |tag: &str| RdoError::Other(format!("Salida inesperada al listar los backups (registro '{}')", tag));
+
+    while let Some(tag) = fields.next() {
+        // Tras el último NUL queda un campo vacío
+        if tag.is_empty() {
+            continue;
+        }
+        let mut next = || fields.next().ok_or_else(|| invalid(&tag));
+        if tag == "D" {
+            folders.push(FolderListing { path: next()?, ..FolderListing::default() });
+            continue;
+        }
+        let folder = folders.last_mut().ok_or_else(|| invalid(&tag))?;
+        match tag.as_str() {
+            "F" => {
+                let name = next()?;
+                folder.stats.insert(name.clone(), parse_stats(&next()?));
+                folder.entries.push(name);
+            }
+            "d" => folder.entries.push(format!("{}/", next()?)),
+            "T" => {
+                let name = next()?;
+                folder.directory_dumps.insert(name.clone(), parse_stats(&next()?));
+                folder.entries.push(format!("{}/", name));
+            }
+            "H" => {
+                let name = next()?;
+                let (start, tar) = (parse_hex(&next()?), parse_hex(&next()?));
+                folder.headers.insert(name, header_from(start, tar));
+            }
+            "C" => {
+                let name = next()?;
+                if let Some(sum) = next()?.split_whitespace().next() {
+                    folder.sums.insert(name, sum.to_string());
+                }
+            }
+            "Z" => {
+                let name = next()?;
+                folder.zip_manifests.insert(name, next()?);
+            }
+            "M" => folder.manifest = Some(next()?),
+            _ => return Err(invalid(&tag)),
+        }
+    }
+    Ok(folders)
+}
+
+// Bytes de la salida de `od -An -tx1`
+fn parse_hex(value: &str) -> Vec<u8> {
+    value.split_whitespace().filter_map(|b| u8::from_str_radix(b, 16).ok()).collect()
+}
+
+// Cabecera con los bytes del principio y la marca de tar en su posición, para DumpFormat::detect
+fn header_from(start: Vec<u8>, tar: Vec<u8>) -> Vec<u8> {
+    let mut header = start;
+    if !tar.is_empty() {
+        header.resize(TAR_MAGIC_OFFSET, 0);
+        header.extend(tar);
+    }
+    header
+}
+
+impl FolderListing {
+    // Ficha del backup con el mismo criterio que `locate_dump`
+    fn describe(&self) -> BackupInfo {
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+        let mut info = BackupInfo { name: name.to_string(), path: self.path.clone(), ..BackupInfo::default() };
+        info.set_date(date_in_name(name));
+
+        let entries: Vec<&str> = self.entries.iter().map(|e| e.as_str()).collect();
+        let candidate = choose_dump(&entries, |dir| Ok(self.directory_dumps.contains_key(dir)));
+        let Ok(Some(candidate)) = candidate else {
+            return info;
+        };
+        let file = candidate.name;
+
+        let (format, compression) = match candidate.kind {
+            CandidateKind::Directory => (Some(DumpFormat::Directory), Compression::None),
+            // Un zip de Odoo lleva un dump.sql
+            CandidateKind::OdooZip => (Some(DumpFormat::Plain), Compression::Zip),
+            CandidateKind::File => {
+                let (format, compression) = Dump::detect(self.headers.get(file).map(|h| h.as_slice()).unwrap_or_default());
+                // El formato de los comprimidos se deduce del nombre
+                (format.or_else(|| DumpFormat::from_name(file)), compression)
+            }
+        };
+        info.format = format;
+        info.compression = Some(compression);
+
+        // Tamaño y fecha: en formato directorio, la suma y el más reciente de sus archivos
+        let stats = match candidate.kind {
+            CandidateKind::Directory => self.directory_dumps.get(file),
+            _ => self.stats.get(file),
+        }.copied().unwrap_or_default();
+
+        // El manifest está junto al dump o, en los zip de Odoo, dentro del zip
+        let manifest = match candidate.kind {
+            CandidateKind::OdooZip => self.zip_manifests.get(file),
+            _ => self.manifest.as_ref(),
+        };
+
+        info.dump = Some(file.to_string());
+        info.fill(stats, self.sums.get(file).cloned(), manifest);
+        info
+    }
+}
+
+impl EntryListing {
+    // Ficha de un dump encontrado con la plantilla de ruta
+    fn describe(&self, path: &str, captures: Captures) -> BackupInfo {
+        let (folder, file) = path.rsplit_once('/').unwrap_or(("", path));
+        let mut info = BackupInfo {
+            name: captures.name,
+            path: folder.to_string(),
+            dump: Some(file.to_string()),
+            ..BackupInfo::default()
+        };
+        // Sin {date} en la plantilla, la fecha puede estar en el nombre
+        info.set_date(captures.date.or_else(|| date_in_name(&info.name)));
+
+        let (format, compression) = match (self.directory, Dump::detect(&self.header)) {
+            (true, _) => (Some(DumpFormat::Directory), Compression::None),
+            // Un zip de Odoo lleva un dump.sql
+            (false, (_, Compression::Zip)) => (Some(DumpFormat::Plain), Compression::Zip),
+            (false, (format, compression)) => (format.or_else(|| DumpFormat::from_name(file)), compression),
+        };
+        info.format = format;
+        info.compression = Some(compression);
+
+        let manifest = match compression {
+            Compression::Zip => self.zip_manifest.as_ref(),
+            _ => self.manifest.as_ref(),
+        };
+        info.fill(self.stats, self.sum.clone(), manifest);
+        info
+    }
+}
+
+impl BackupInfo {
+    // Completar tamaño, fecha, suma y los datos del manifest.json
+    fn fill(&mut self, (size, mtime): (u64, Option<i64>), sha256: Option<String>, manifest: Option<&String>) {
+        self.size = Some(size);
+        self.mtime = mtime;
+        self.modified = mtime.and_then(|t| DateTime::from_timestamp(t, 0)).map(|t| t.with_timezone(&Local).to_rfc3339());
+        self.sha256 = sha256;
+        if let Some(manifest) = manifest.and_then(|m| Manifest::from_reader(m.as_bytes()).ok()) {
+            self.odoo_version = manifest.version.or(manifest.major_version);
+            self.db_name = manifest.db_name;
+        }
+    }
+
+    // Dump para restaurar; el formato de los comprimidos se conoce al descomprimir
+    fn to_dump(&self) -> Dump {
+        let (format, compression) = match self.compression {
+            None | Some(Compression::None) => (self.format, Compression::None),
+            Some(compression) => (None, compression),
+        };
+        Dump { path: self.dump_path().unwrap_or_default(), location: Location::Container, format, compression }
+    }
+}
+
+// Sumar los tamaños y quedarse con la fecha más reciente de las líneas "<bytes> <segundos>"
+fn parse_stats(stats: &str) -> (u64, Option<i64>) {
+    let mut size = 0;
+    let mut mtime = None;
+    for line in stats.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(Ok(bytes)), Some(Ok(time))) = (fields.next().map(str::parse::<u64>), fields.next().map(str::parse::<i64>)) {
+            size += bytes;
+            mtime = mtime.max(Some(time));
+        }
+    }
+    (size, mtime)
+}
+
+// Aplicar el filtro y el orden de `rdo backups list`
+pub fn select(backups: Vec<BackupInfo>, opts: &ListArgs) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = backups.into_iter()
+        .filter(|b| opts.filter.as_ref().is_none_or(|f| b.name.contains(f.as_str())))
+        .filter(|b| opts.format.is_none_or(|f| b.format == Some(f)))
+        .collect();
+
+    match opts.sort {
+        BackupSort::Name => backups.sort_by(|a, b| a.name.cmp(&b.name)),
+        // Mismo criterio que --latest: el último de la lista es el que restauraría
+        BackupSort::Date => backups.sort_by(|a, b| a.recency().cmp(&b.recency()).then_with(|| a.name.cmp(&b.name))),
+        BackupSort::Size => backups.sort_by(|a, b| a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name))),
+    }
+    if opts.reverse {
+        backups.reverse();
+    }
+    backups
+}
+
+// Tabla con una fila por backup; las carpetas sin dump se marcan con X.
+// La columna FECHA solo aparece si algún backup tiene fecha en la ruta o en el nombre
+fn print_table(backups: &[BackupInfo], checksum: bool) {
+    let dash = || "-".to_string();
+    let dated = backups.iter().any(|b| b.date.is_some());
+    let mut rows = vec![vec![
+        String::new(), "NOMBRE".into(), "DUMP".into(), "FORMATO".into(), "COMPRESIÓN".into(),
+        "TAMAÑO".into(), "MODIFICADO".into(), "ODOO".into(), "BASE ORIGINAL".into(),
+    ]];
+    if dated {
+        rows[0].insert(2, "FECHA".into());
+    }
+    if checksum {
+        rows[0].push("SHA256".into());
+    }
+
+    for b in backups {
+        let mut row = vec![
+            if b.dump.is_some() { "✓" } else { "X" }.to_string(),
+            b.name.clone(),
+            b.dump.clone().unwrap_or_else(dash),
+            b.format.map(|f| f.to_string()).unwrap_or_else(dash),
+            b.compression_label(),
+            b.size_label(),
+            b.date_label(),
+            b.odoo_version.clone().unwrap_or_else(dash),
+            b.db_name.clone().unwrap_or_else(dash),
+        ];
+        if dated {
+            row.insert(2, b.date.clone().unwrap_or_else(dash));
+        }
+        if checksum {
+            row.push(b.sha256.clone().unwrap_or_else(dash));
+        }
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let cells: Vec<String> = row.iter().zip(&widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{FakeRuntime, Runtime};
+
+    #[test]
+    fn catalog_is_one_container_command() {
+        let listing = "D\0/b/prod\0F\0dump.sql.bz2\x002048 1745836800\0H\0dump.sql.bz2\0 42 5a 68 39 31 41 59 26\0\0\
+            C\0dump.sql.bz2\0abc123  -\0F\0manifest.json\x0010 1745836000\0\
+            M\0{\"db_name\": \"prod\", \"version\": \"17.0\"}\0D\0/b/vacia\0";
+        let fake = FakeRuntime::new(move |_| (0, listing.into(), String::new()));
+        let p = Profile {
+            container_id: Some("ctr".into()),
+            dir_backup: Some("/b".into()),
+            executor: Some(fake.clone()),
+            ..Profile::default()
+        };
+        let backups = p.backup_catalog(true).unwrap();
+
+        let calls = fake.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0][..3], ["ctr", "sh", "-c"]);
+        assert_eq!(calls[0][4..], ["rdo-list", "/b", "1"]);
+
+        assert_eq!(backups.len(), 2);
+        let prod = &backups[0];
+        assert_eq!(prod.dump.as_deref(), Some("dump.sql.bz2"));
+        assert_eq!(prod.format, Some(DumpFormat::Plain));
+        assert_eq!(prod.compression, Some(Compression::Bzip2));
+        assert_eq!((prod.size, prod.mtime), (Some(2048), Some(1745836800)));
+        assert_eq!(prod.sha256.as_deref(), Some("abc123"));
+        assert_eq!(prod.odoo_version.as_deref(), Some("17.0"));
+        assert_eq!(prod.db_name.as_deref(), Some("prod"));
+        assert_eq!(backups[1], BackupInfo { name: "vacia".into(), path: "/b/vacia".into(), ..BackupInfo::default() });
+    }
+
+    // El script de verdad, con el runtime local sobre un directorio temporal
+    #[test]
+    fn list_script_reads_every_kind_of_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            path
+        };
+        std::fs::write(folder("custom").join("dump.dump"), b"PGDMP\x01\x0e\x00resto").unwrap();
+        std::fs::write(folder("plano").join("dump.sql"), b"SELECT 1;\n").unwrap();
+        std::fs::write(folder("plano").join("manifest.json"), br#"{"version": "16.0", "db_name": "orig"}"#).unwrap();
+        std::fs::create_dir_all(folder("directorio").join("dump")).unwrap();
+        std::fs::write(folder("directorio").join("dump/toc.dat"), vec![0; 100]).unwrap();
+        std::fs::write(folder("directorio").join("dump/3001.dat.gz"), vec![0; 50]).unwrap();
+        std::fs::write(folder("con espacio ' y $(x)").join("dump.sql.gz"), [0x1f, 0x8b, 8, 0]).unwrap();
+        folder("vacia");
+
+        let p = Profile {
+            runtime: Some(Runtime::Local),
+            dir_backup: Some(dir.path().to_string_lossy().into_owned()),
+            ..Profile::default()
+        };
+        let backups = p.backup_catalog(true).unwrap();
+        let by_name = |name: &str| backups.iter().find(|b| b.name == name).unwrap_or_else(|| panic!("falta {}", name));
+
+        assert_eq!(backups.len(), 5);
+        assert_eq!(by_name("custom").format, Some(DumpFormat::Custom));
+        assert_eq!(by_name("custom").sha256.as_ref().map(|s| s.len()), Some(64));
+        assert_eq!(by_name("plano").format, Some(DumpFormat::Plain));
+        assert_eq!(by_name("plano").odoo_version.as_deref(), Some("16.0"));
+        assert_eq!(by_name("directorio").format, Some(DumpFormat::Directory));
+        assert_eq!(by_name("directorio").size, Some(150));
+        assert_eq!(by_name("con espacio ' y $(x)").compression, Some(Compression::Gzip));
+        assert_eq!(by_name("con espacio ' y $(x)").format, Some(DumpFormat::Plain));
+        assert_eq!(by_name("vacia").dump, None);
+    }
+
+    // Plantilla con fecha: listado y restauración encuentran los mismos dumps
+    #[test]
+    fn template_catalog_and_restore_agree() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &[u8]| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("prod/2025-04-27/prod.dump", b"PGDMP\x01\x0e\x00");
+        write("prod/2025-04-28/prod.sql.gz", &[0x1f, 0x8b, 8, 0]);
+        write("prod/2025-04-28/manifest.json", br#"{"db_name": "prod"}"#);
+        write("prod/2025-04-28/.prod.sql.gz.partial", b"");
+        write("prod/sin-fecha/prod.sql", b"SELECT 1;\n");
+        write("stage/2025-01-01/stage.tar", b"");
+
+        let dir_backup = dir.path().to_string_lossy().into_owned();
+        let p = Profile {
+            runtime: Some(Runtime::Local),
+            dir_backup: Some(dir_backup.clone()),
+            path_template: Some("{dir}/{name}/{date}/{name}.{ext}".into()),
+            ..Profile::default()
+        };
+        let backups = p.backup_catalog(false).unwrap();
+        let found: Vec<(&str, Option<&str>)> = backups.iter().map(|b| (b.name.as_str(), b.date.as_deref())).collect();
+        assert_eq!(found, [("prod", Some("2025-04-27")), ("prod", Some("2025-04-28")), ("stage", Some("2025-01-01"))]);
+        assert_eq!(backups[0].format, Some(DumpFormat::Custom));
+        assert_eq!((backups[1].format, backups[1].compression), (Some(DumpFormat::Plain), Some(Compression::Gzip)));
+        assert_eq!(backups[1].db_name.as_deref(), Some("prod"));
+
+        // Por nombre, el más reciente; por ruta, ese mismo
+        let newest = p.locate_dump("prod").unwrap();
+        assert_eq!(newest.path, format!("{}/prod/2025-04-28/prod.sql.gz", dir_backup));
+        assert_eq!((newest.format, newest.compression), (None, Compression::Gzip));
+        let older = backups[0].dump_path().unwrap();
+        assert_eq!(p.locate_dump(&older).unwrap().format, Some(DumpFormat::Custom));
+        assert!(matches!(p.locate_dump("falta"), Err(RdoError::MissingDump(_))));
+    }
+
+    #[test]
+    fn latest_prefers_the_date_in_the_name_over_mtime() {
+        let backup = |name: &str, mtime: i64| {
+            let mut b = BackupInfo { name: name.into(), dump: Some("dump.sql".into()), mtime: Some(mtime), ..BackupInfo::default() };
+            b.set_date(date_in_name(name));
+            b
+        };
+        let all = vec![
+            backup("produccion_04_28_2025", 10),
+            // Copiado después: más reciente por mtime, pero con fecha anterior
+            backup("produccion_12_31_2024", 99),
+            backup("produccion_eu_05_01_2025", 5),
+            backup("staging_06_01_2025", 1),
+            BackupInfo { name: "produccion_06_30_2025".into(), ..BackupInfo::default() },
+        ];
+        let latest = |pattern: &str| newest(&matching_backups(all.clone(), pattern)).map(|b| b.name.clone());
+
+        assert_eq!(latest("produccion").as_deref(), Some("produccion_eu_05_01_2025"));
+        assert_eq!(latest("produccion_??_??_2025").as_deref(), Some("produccion_04_28_2025"));
+        assert_eq!(latest("nada"), None);
+
+        // Sin fechas en los nombres decide la de modificación
+        let undated = vec![backup("copia_a", 3), backup("copia_b", 7), backup("copia_c", 5)];
+        assert_eq!(newest(&undated).map(|b| b.name.as_str()), Some("copia_b"));
+
+        // `backups list --sort date` ordena igual: el último es el que elige --latest
+        let dated: Vec<BackupInfo> = all.into_iter().filter(|b| b.dump.is_some()).collect();
+        let sorted = select(dated.clone(), &ListArgs { sort: BackupSort::Date, ..ListArgs::default() });
+        let names: Vec<&str> = sorted.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["produccion_12_31_2024", "produccion_04_28_2025", "produccion_eu_05_01_2025", "staging_06_01_2025"]);
+        assert_eq!(sorted.last().map(|b| &b.name), newest(&dated).map(|b| &b.name));
+
+        let mixed = vec![backup("copia_sin_fecha", 99), backup("prod_2025-01-01", 1)];
+        let sorted = select(mixed, &ListArgs { sort: BackupSort::Date, reverse: true, ..ListArgs::default() });
+        assert_eq!(sorted[0].name, "prod_2025-01-01");
+    }
+
+    #[test]
+    fn directory_dumps_add_up_their_files() {
+        assert_eq!(parse_stats("100 10\n200 30\n50 20\n"), (350, Some(30)));
+        assert_eq!(parse_stats(""), (0, None));
+    }
+
+    #[test]
+    fn select_filters_and_sorts() {
+        let backup = |name: &str, size: u64, mtime: i64, format: DumpFormat| BackupInfo {
+            name: name.into(),
+            size: Some(size),
+            mtime: Some(mtime),
+            format: Some(format),
+            ..BackupInfo::default()
+        };
+        let all = vec![
+            backup("prod_a", 30, 1, DumpFormat::Custom),
+            backup("prod_b", 10, 3, DumpFormat::Plain),
+            backup("test", 20, 2, DumpFormat::Custom),
+        ];
+        let names = |opts: ListArgs| select(all.clone(), &opts).into_iter().map(|b| b.name).collect::<Vec<_>>();
+
+        assert_eq!(names(ListArgs { sort: BackupSort::Date, reverse: true, ..ListArgs::default() }), ["prod_b", "test", "prod_a"]);
+        assert_eq!(names(ListArgs { sort: BackupSort::Size, ..ListArgs::default() }), ["prod_b", "test", "prod_a"]);
+        assert_eq!(names(ListArgs { filter: Some("prod".into()), format: Some(DumpFormat::Custom), ..ListArgs::default() }), ["prod_a"]);
+    }
+}
@@ -0,0 +1,487 @@
+use clap::{ArgAction, ArgGroup, Parser, Subcommand, ValueEnum};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::dump::{Compression, DumpFormat};
+use crate::profile::Profile;
+use crate::runtime::Runtime;
+
+/// Herramienta para restaurar bases de datos Odoo en contenedores Docker
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+#[command(group(ArgGroup::new("legacy").args(["view_profile", "view_backups", "run"])))]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Perfil a usar (por defecto, RDO_PROFILE o el perfil predeterminado)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Mostrar la salida de psql y pg_restore en vivo (-v avisos y errores, -vv toda)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Sin barra de progreso ni mensajes de psql y pg_restore; solo errores
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Responder "s" a todas las confirmaciones
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// No preguntar nunca: si hace falta una respuesta, terminar con el código 3
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Crear la base de datos destino si no existe, sin preguntar
+    #[arg(long, global = true)]
+    pub create_if_missing: bool,
+
+    /// Archivo de registro de esta ejecución (por defecto, ~/.local/state/rdo/logs/<fecha>-<comando>.log)
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// [Obsoleto] Ver perfil guardado; use `rdo profile show`
+    #[arg(long = "vp")]
+    pub view_profile: bool,
+
+    /// [Obsoleto] Ver backups; use `rdo backups list`
+    #[arg(long = "vb")]
+    pub view_backups: bool,
+
+    /// [Obsoleto] Nombre de la base de datos destino; use `rdo restore <namedb>`
+    #[arg(short, long)]
+    pub namedb: Option<String>,
+
+    /// [Obsoleto] Ejecutar el comando de restauración; use `rdo restore <namedb>`
+    #[arg(short, long, requires = "namedb")]
+    pub run: bool,
+}
+
+/// Datos de conexión que se pueden indicar en la línea de comandos
+#[derive(clap::Args, Debug, Clone, Default, PartialEq)]
+pub struct ConnectionArgs {
+    /// Host de la base de datos
+    #[arg(short, long)]
+    pub xhost: Option<String>,
+
+    /// Puerto de la base de datos
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// Usuario de la base de datos
+    #[arg(short, long)]
+    pub username: Option<String>,
+
+    /// Contraseña de la base de datos (opcional, se puede usar PGPASSWORD, ~/.pgpass o --password-command)
+    #[arg(short = 'w', long)]
+    pub password: Option<String>,
+
+    /// Orden que imprime la contraseña, como `pass show db/staging`; se ejecuta al conectar
+    #[arg(long)]
+    pub password_command: Option<String>,
+
+    /// Contenedor: ID, nombre, <proyecto>/<servicio> de compose o label:clave=valor
+    #[arg(short, long, alias = "container_id")]
+    pub container_id: Option<String>,
+
+    /// Ruta base para backups (dentro del contenedor)
+    #[arg(short, long, alias = "dir_backup")]
+    pub dir_backup: Option<String>,
+
+    /// Plantilla de la ruta de los dumps, como `{dir}/{name}/{date}/{name}.{ext}` (admite * y ?)
+    #[arg(long)]
+    pub path_template: Option<String>,
+
+    /// Nombre de la base de datos destino de `restore --from` sin --to, como `{backup}_{date}`
+    #[arg(long)]
+    pub target_template: Option<String>,
+
+    /// Directorio de datos de Odoo donde se copia el filestore
+    #[arg(long)]
+    pub data_dir: Option<String>,
+
+    /// Contenedor de Odoo donde está --data-dir (si no se indica, es una ruta del host)
+    #[arg(long)]
+    pub odoo_container: Option<String>,
+
+    /// Programa para ejecutar las órdenes: docker, podman, nerdctl o local (psql en el host, sin contenedor)
+    #[arg(long, value_enum)]
+    pub runtime: Option<Runtime>,
+}
+
+impl Args {
+    // Las banderas antiguas y la conexión global solo valen sin subcomando
+    pub fn uses_legacy_flags(&self) -> bool {
+        self.view_profile || self.view_backups || self.run || self.namedb.is_some()
+            || self.conn != ConnectionArgs::default()
+    }
+}
+
+impl ConnectionArgs {
+    pub fn to_profile(&self) -> Profile {
+        Profile {
+            xhost: self.xhost.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            password: self.password.clone(),
+            password_command: self.password_command.clone(),
+            container_id: self.container_id.clone(),
+            dir_backup: self.dir_backup.clone(),
+            path_template: self.path_template.clone(),
+            target_template: self.target_template.clone(),
+            data_dir: self.data_dir.clone(),
+            odoo_container: self.odoo_container.clone(),
+            runtime: self.runtime,
+            executor: None,
+        }
+    }
+}
+
+/// Opciones para dumps en formato custom, directorio o tar (pg_restore)
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RestoreArgs {
+    /// Trabajos en paralelo de pg_restore (formatos custom y directorio)
+    #[arg(short, long)]
+    pub jobs: Option<u32>,
+
+    /// No restaurar los propietarios de los objetos
+    #[arg(long)]
+    pub no_owner: bool,
+
+    /// Eliminar los objetos existentes antes de recrearlos
+    #[arg(long)]
+    pub clean: bool,
+
+    /// Si la base de datos destino existe, cerrar sus conexiones, eliminarla y crearla vacía antes de restaurar (pide escribir su nombre salvo con --yes)
+    #[arg(long, conflicts_with = "rename_existing")]
+    pub replace: bool,
+
+    /// Si la base de datos destino existe, conservarla como <nombre>_<AAAAMMDD_HHMMSS> y restaurar en una nueva
+    #[arg(long)]
+    pub rename_existing: bool,
+
+    /// El filestore de un zip de Odoo sustituye al que ya hubiera en lugar de mezclarse con él (lo activa --replace)
+    #[arg(skip)]
+    pub replace_filestore: bool,
+
+    /// Dump en el host (host:/ruta/al/dump); se envía al contenedor sin copiarlo
+    #[arg(long, value_parser = parse_source, value_name = "host:RUTA")]
+    pub source: Option<PathBuf>,
+
+    /// Backup ZIP de Odoo en el host (dump.sql + filestore + manifest.json); equivale a --source host:<ruta>
+    #[arg(long, conflicts_with = "source")]
+    pub zip: Option<PathBuf>,
+
+    /// Mostrar junto a la barra de progreso el último mensaje de psql o pg_restore
+    #[arg(long)]
+    pub tail: bool,
+
+    /// Dónde descomprimir los dumps comprimidos (.gz, .zst, .xz, .bz2)
+    #[arg(long, value_enum, default_value_t = DecompressAt::Host)]
+    pub decompress: DecompressAt,
+}
+
+impl RestoreArgs {
+    // Dump del host indicado con --source o --zip
+    pub fn host_source(&self) -> Option<&PathBuf> {
+        self.source.as_ref().or(self.zip.as_ref())
+    }
+}
+
+// Interpretar --source: por ahora solo se admiten rutas del host
+fn parse_source(value: &str) -> Result<PathBuf, String> {
+    match value.split_once(':') {
+        Some(("host", path)) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Err("use host:/ruta/al/dump".to_string()),
+    }
+}
+
+/// Opciones de `rdo backup`
+#[derive(clap::Args, Debug, Clone)]
+pub struct BackupArgs {
+    /// Formato de pg_dump
+    #[arg(short = 'F', long, value_enum, default_value_t = DumpFormat::Plain)]
+    pub format: DumpFormat,
+
+    /// Compresión del archivo (solo plain y tar; custom y directory ya los comprime pg_dump)
+    #[arg(short = 'Z', long, value_enum, default_value_t = Compression::None)]
+    pub compress: Compression,
+
+    /// Trabajos en paralelo de pg_dump (solo formato directory)
+    #[arg(short, long)]
+    pub jobs: Option<u32>,
+
+    /// No incluir los propietarios de los objetos en el dump
+    #[arg(long)]
+    pub no_owner: bool,
+
+    /// Crear un zip como el del gestor de bases de datos de Odoo (dump.sql, manifest.json y filestore de --data-dir)
+    #[arg(long, conflicts_with_all = ["format", "compress", "jobs"])]
+    pub zip: bool,
+
+    /// Reemplazar el dump que ya haya en la carpeta del backup
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Lugar donde se descomprime un dump comprimido
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum DecompressAt {
+    /// En el host, con descompresores integrados en rdo
+    #[default]
+    Host,
+    /// En el contenedor, con gzip, zstd, xz o bzip2 instalados en él
+    Container,
+}
+
+impl fmt::Display for DecompressAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecompressAt::Host => write!(f, "host"),
+            DecompressAt::Container => write!(f, "contenedor"),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Gestionar perfiles guardados
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Consultar los backups disponibles en el contenedor
+    Backups {
+        #[command(subcommand)]
+        action: BackupsAction,
+    },
+
+    /// Restaurar un backup en una base de datos (dump.sql, dump.dump, dump.tar, dump/ o comprimidos)
+    Restore {
+        /// Backup a restaurar, en la base de datos del mismo nombre salvo con --to; sin él, --from ni --latest se abre un selector de backups
+        #[arg(conflicts_with_all = ["from", "latest"])]
+        namedb: Option<String>,
+
+        /// Backup a restaurar (con plantilla de ruta, también la ruta de uno de sus dumps)
+        #[arg(long, conflicts_with = "latest")]
+        from: Option<String>,
+
+        /// Restaurar el backup más reciente cuyo nombre empieza por este prefijo o coincide con este patrón (* y ?), como con --from
+        #[arg(long, value_name = "PREFIJO")]
+        latest: Option<String>,
+
+        /// Base de datos destino; con --from y sin --to, la de target_template (`{backup}_{date}`)
+        #[arg(long)]
+        to: Option<String>,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        #[command(flatten)]
+        restore: RestoreArgs,
+    },
+
+    /// Crear un backup con pg_dump en <dir_backup>/<namedb>/
+    Backup {
+        /// Base de datos a copiar (y nombre de la carpeta del backup)
+        namedb: String,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        #[command(flatten)]
+        backup: BackupArgs,
+    },
+
+    /// Gestionar las bases de datos del servidor
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
+    /// Consultar la configuración efectiva
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Buscar contenedores de PostgreSQL u Odoo en marcha y ofrecer guardarlos como perfil
+    Discover {
+        /// Programa con que buscar los contenedores: docker, podman o nerdctl
+        #[arg(long, value_enum)]
+        runtime: Option<Runtime>,
+
+        /// Guardar sin preguntar el contenedor con este número del listado
+        #[arg(long)]
+        save: Option<usize>,
+
+        /// Nombre del perfil que se guarda (por defecto, el servicio de compose o el nombre del contenedor)
+        #[arg(long, requires = "save")]
+        name: Option<String>,
+    },
+}
+
+impl Commands {
+    // Nombre del subcomando, para el archivo de registro
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commands::Profile { .. } => "profile",
+            Commands::Backups { .. } => "backups",
+            Commands::Restore { .. } => "restore",
+            Commands::Backup { .. } => "backup",
+            Commands::Db { .. } => "db",
+            Commands::Config { .. } => "config",
+            Commands::Discover { .. } => "discover",
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileAction {
+    /// Listar perfiles (* marca el predeterminado)
+    List,
+
+    /// Mostrar un perfil (por defecto, el predeterminado)
+    Show {
+        name: Option<String>,
+    },
+
+    /// Guardar un perfil, creándolo o reemplazándolo (por defecto, el de --profile o el predeterminado)
+    Save {
+        name: Option<String>,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Crear un perfil nuevo
+    Create {
+        name: String,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Actualizar los campos indicados de un perfil
+    Update {
+        name: String,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Renombrar un perfil
+    Rename {
+        from: String,
+        to: String,
+    },
+
+    /// Eliminar un perfil
+    Delete {
+        name: String,
+    },
+
+    /// Marcar un perfil como predeterminado
+    Default {
+        name: String,
+    },
+
+    /// Desbloquear las contraseñas cifradas para esta sesión: eval "$(rdo profile unlock)"
+    Unlock,
+
+    /// Cifrar todas las contraseñas con una frase de paso nueva (también las guardadas en claro)
+    Rekey,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupsAction {
+    /// Listar los backups con su dump, formato, tamaño, fecha y versión de Odoo
+    List {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+}
+
+/// Orden del catálogo de backups
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum BackupSort {
+    /// Por nombre de la carpeta
+    #[default]
+    Name,
+    /// Por la fecha del nombre o de la plantilla y, a igual fecha o sin ella, por la de modificación del dump
+    Date,
+    /// Por tamaño del dump
+    Size,
+}
+
+/// Opciones de `rdo backups list`
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ListArgs {
+    /// Orden de la lista
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: BackupSort,
+
+    /// Invertir el orden (los más recientes o más grandes primero)
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Mostrar solo los backups cuyo nombre contiene este texto
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Mostrar solo los dumps de este formato
+    #[arg(long, value_enum)]
+    pub format: Option<DumpFormat>,
+
+    /// Calcular el sha256 de cada dump (lee el archivo completo)
+    #[arg(long)]
+    pub checksum: bool,
+
+    /// Salida en JSON, para scripts
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbAction {
+    /// Crear una base de datos vacía
+    Create {
+        namedb: String,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Eliminar una base de datos
+    Drop {
+        namedb: String,
+
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+
+    /// Listar las bases de datos del servidor
+    List {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Mostrar el valor efectivo de cada campo y de dónde sale (CLI > RDO_* > perfil > por defecto)
+    Explain {
+        #[command(flatten)]
+        conn: ConnectionArgs,
+    },
+}
@@ -0,0 +1,286 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::RdoError;
+use crate::profile::{Profile, ProfileStore};
+use crate::runtime::Runtime;
+use crate::template::DEFAULT_TARGET_TEMPLATE;
+
+/// Valores por defecto cuando ninguna otra fuente define el campo
+const DEFAULT_XHOST: &str = "localhost";
+const DEFAULT_PORT: u16 = 5432;
+const DEFAULT_USERNAME: &str = "odoo";
+const DEFAULT_DIR_BACKUP: &str = "/tmp/backups";
+
+/// Variable de entorno para elegir el perfil sin usar --profile
+const PROFILE_ENV: &str = "RDO_PROFILE";
+
+/// Origen de un valor de configuración, de mayor a menor prioridad
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Cli,
+    Env(&'static str),
+    Profile(String),
+    Default,
+    Unset,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Cli => write!(f, "línea de comandos"),
+            Source::Env(var) => write!(f, "entorno ({})", var),
+            Source::Profile(name) => write!(f, "perfil '{}'", name),
+            Source::Default => write!(f, "valor por defecto"),
+            Source::Unset => write!(f, "sin definir"),
+        }
+    }
+}
+
+/// Valor efectivo de un campo y de dónde salió
+#[derive(Debug, Clone)]
+pub struct Origin {
+    pub field: &'static str,
+    pub value: Option<String>,
+    pub source: Source,
+    pub secret: bool,
+}
+
+/// Configuración efectiva: perfil combinado con la línea de comandos, el entorno y los valores por defecto
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub profile_name: Option<String>,
+    pub profile_source: Source,
+    pub profile: Profile,
+    pub origins: Vec<Origin>,
+}
+
+impl Config {
+    // Combinar todas las fuentes: CLI > RDO_* > perfil > valores por defecto
+    pub fn resolve(store: &ProfileStore, requested: Option<&str>, cli: &Profile) -> Result<Self, RdoError> {
+        Self::resolve_with(store, requested, cli, &|var| env::var(var).ok())
+    }
+
+    // Igual que `resolve`, leyendo las variables de entorno con `env`
+    fn resolve_with(store: &ProfileStore, requested: Option<&str>, cli: &Profile, env: &dyn Fn(&str) -> Option<String>) -> Result<Self, RdoError> {
+        // El perfil se elige con --profile, RDO_PROFILE o el predeterminado
+        let env_profile = env(PROFILE_ENV).filter(|s| !s.is_empty());
+        let (requested, profile_source) = match (requested, env_profile.as_deref()) {
+            (Some(name), _) => (Some(name), Source::Cli),
+            (None, Some(name)) => (Some(name), Source::Env(PROFILE_ENV)),
+            (None, None) => (None, Source::Default),
+        };
+
+        let (profile_name, saved) = match store.load(requested) {
+            Ok(p) => (store.resolve_name(requested), p),
+            // Si se pidió un perfil concreto y no existe, es un error
+            Err(e) if requested.is_some() => return Err(RdoError::Config(e.to_string())),
+            // Sin perfil predeterminado trabajamos con el resto de fuentes
+            Err(_) => (None, Profile::default()),
+        };
+        let profile_label = profile_name.clone().unwrap_or_default();
+
+        let mut origins = Vec::new();
+        let mut layer = |field: &'static str, cli: Option<String>, env_vars: &[&'static str], saved: Option<String>, default: Option<&str>, secret: bool| {
+            let (value, source) = pick(cli, env_vars, env, saved, &profile_label, default);
+            origins.push(Origin { field, value: value.clone(), source, secret });
+            value
+        };
+
+        let xhost = layer("xhost", cli.xhost.clone(), &["RDO_XHOST"], saved.xhost.clone(), Some(DEFAULT_XHOST), false);
+        let port = layer("port", cli.port.map(|p| p.to_string()), &["RDO_PORT"], saved.port.map(|p| p.to_string()), Some(&DEFAULT_PORT.to_string()), false);
+        let username = layer("username", cli.username.clone(), &["RDO_USERNAME"], saved.username.clone(), Some(DEFAULT_USERNAME), false);
+        // PGPASSWORD se mantiene como alternativa a RDO_PASSWORD
+        let password = layer("password", cli.password.clone(), &["RDO_PASSWORD", "PGPASSWORD"], saved.password.clone(), None, true);
+        let password_command = layer("password_command", cli.password_command.clone(), &["RDO_PASSWORD_COMMAND"], saved.password_command.clone(), None, false);
+        let container_id = layer("container_id", cli.container_id.clone(), &["RDO_CONTAINER_ID"], saved.container_id.clone(), None, false);
+        let dir_backup = layer("dir_backup", cli.dir_backup.clone(), &["RDO_DIR_BACKUP"], saved.dir_backup.clone(), Some(DEFAULT_DIR_BACKUP), false);
+        let path_template = layer("path_template", cli.path_template.clone(), &["RDO_PATH_TEMPLATE"], saved.path_template.clone(), None, false);
+        let target_template = layer("target_template", cli.target_template.clone(), &["RDO_TARGET_TEMPLATE"], saved.target_template.clone(), Some(DEFAULT_TARGET_TEMPLATE), false);
+        let data_dir = layer("data_dir", cli.data_dir.clone(), &["RDO_DATA_DIR"], saved.data_dir.clone(), None, false);
+        let odoo_container = layer("odoo_container", cli.odoo_container.clone(), &["RDO_ODOO_CONTAINER"], saved.odoo_container.clone(), None, false);
+        let runtime = layer("runtime", cli.runtime.map(|r| r.to_string()), &["RDO_RUNTIME"], saved.runtime.map(|r| r.to_string()), Some(&Runtime::default().to_string()), false);
+
+        let profile = Profile {
+            xhost,
+            port: parse_field("port", port)?,
+            username,
+            password,
+            password_command,
+            container_id,
+            dir_backup,
+            path_template,
+            target_template,
+            data_dir,
+            odoo_container,
+            runtime: parse_field("runtime", runtime)?,
+            executor: None,
+        };
+
+        Ok(Self { profile_name, profile_source, profile, origins })
+    }
+
+    // Mostrar de dónde sale cada valor efectivo
+    pub fn explain(&self) {
+        print!("{}", self.report());
+    }
+
+    fn report(&self) -> String {
+        let mut report = match &self.profile_name {
+            Some(name) if self.profile_source == Source::Default => format!("Perfil: {} (predeterminado)\n", name),
+            Some(name) => format!("Perfil: {} (elegido por {})\n", name, self.profile_source),
+            None => "Perfil: ninguno\n".to_string(),
+        };
+        report.push('\n');
+
+        let width = self.origins.iter().map(|o| o.field.len()).max().unwrap_or(0);
+        for origin in &self.origins {
+            let value = match &origin.value {
+                Some(_) if origin.secret => "********".to_string(),
+                Some(v) => v.clone(),
+                None => "-".to_string(),
+            };
+            report.push_str(&format!("{:width$}  {:30}  {}\n", origin.field, value, origin.source, width = width));
+        }
+        report
+    }
+}
+
+// Elegir el primer valor definido siguiendo la precedencia
+fn pick(cli: Option<String>, env_vars: &[&'static str], env: &dyn Fn(&str) -> Option<String>, saved: Option<String>, profile_name: &str, default: Option<&str>) -> (Option<String>, Source) {
+    if let Some(value) = cli {
+        return (Some(value), Source::Cli);
+    }
+    for var in env_vars {
+        if let Some(value) = env(var) {
+            if !value.is_empty() {
+                return (Some(value), Source::Env(var));
+            }
+        }
+    }
+    if let Some(value) = saved {
+        return (Some(value), Source::Profile(profile_name.to_string()));
+    }
+    match default {
+        Some(value) => (Some(value.to_string()), Source::Default),
+        None => (None, Source::Unset),
+    }
+}
+
+// Convertir un valor textual (por ejemplo, de RDO_PORT) al tipo del campo
+fn parse_field<T: FromStr>(field: &str, value: Option<String>) -> Result<Option<T>, RdoError> {
+    match value {
+        Some(v) => v.parse().map(Some)
+            .map_err(|_| RdoError::Config(format!("Valor inválido para {}: '{}'", field, v))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Almacén con el perfil predeterminado "prod" y otro perfil "dev"
+    fn store(dir: &tempfile::TempDir) -> ProfileStore {
+        let mut store = ProfileStore::open_at(dir.path().join("profiles.json")).unwrap();
+        let prod = Profile {
+            xhost: Some("db-perfil".into()),
+            port: Some(5000),
+            username: Some("perfil".into()),
+            container_id: Some("ctr-perfil".into()),
+            ..Profile::default()
+        };
+        store.create("prod", prod).unwrap();
+        store.create("dev", Profile { xhost: Some("db-dev".into()), ..Profile::default() }).unwrap();
+        store
+    }
+
+    fn resolve(store: &ProfileStore, requested: Option<&str>, cli: &Profile, vars: &[(&str, &str)]) -> Config {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::resolve_with(store, requested, cli, &|var| vars.get(var).cloned()).unwrap()
+    }
+
+    fn source<'a>(config: &'a Config, field: &str) -> &'a Source {
+        &config.origins.iter().find(|o| o.field == field).unwrap().source
+    }
+
+    const ENV: &[(&str, &str)] = &[("RDO_XHOST", "db-env"), ("RDO_PORT", "6000"), ("RDO_USERNAME", "env"), ("RDO_CONTAINER_ID", "ctr-env")];
+
+    #[test]
+    fn command_line_wins_over_environment_and_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        let cli = Profile { xhost: Some("db-cli".into()), port: Some(7000), username: Some("cli".into()), ..Profile::default() };
+        let config = resolve(&store, None, &cli, ENV);
+
+        assert_eq!(config.profile.xhost.as_deref(), Some("db-cli"));
+        assert_eq!(config.profile.port, Some(7000));
+        assert_eq!(config.profile.username.as_deref(), Some("cli"));
+        for field in ["xhost", "port", "username"] {
+            assert_eq!(source(&config, field), &Source::Cli, "{}", field);
+        }
+        // Lo que no viene en la línea de comandos sale del entorno
+        assert_eq!(config.profile.container_id.as_deref(), Some("ctr-env"));
+        assert_eq!(source(&config, "container_id"), &Source::Env("RDO_CONTAINER_ID"));
+    }
+
+    #[test]
+    fn environment_wins_over_profile_and_profile_over_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        let config = resolve(&store, None, &Profile::default(), &ENV[..2]);
+        assert_eq!(config.profile.xhost.as_deref(), Some("db-env"));
+        assert_eq!(config.profile.port, Some(6000));
+        assert_eq!(source(&config, "port"), &Source::Env("RDO_PORT"));
+        assert_eq!(config.profile.username.as_deref(), Some("perfil"));
+        assert_eq!(source(&config, "username"), &Source::Profile("prod".into()));
+        assert_eq!(config.profile.dir_backup.as_deref(), Some(DEFAULT_DIR_BACKUP));
+        assert_eq!(source(&config, "dir_backup"), &Source::Default);
+        assert_eq!(source(&config, "data_dir"), &Source::Unset);
+
+        // Una variable vacía no cuenta; PGPASSWORD sirve si no hay RDO_PASSWORD
+        let config = resolve(&store, None, &Profile::default(), &[("RDO_XHOST", ""), ("PGPASSWORD", "pg")]);
+        assert_eq!(config.profile.xhost.as_deref(), Some("db-perfil"));
+        assert_eq!(source(&config, "password"), &Source::Env("PGPASSWORD"));
+        assert!(Config::resolve_with(&store, None, &Profile::default(), &|var| (var == "RDO_PORT").then(|| "x".to_string())).is_err());
+    }
+
+    #[test]
+    fn profile_comes_from_the_flag_the_environment_or_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+
+        let config = resolve(&store, Some("dev"), &Profile::default(), &[("RDO_PROFILE", "prod")]);
+        assert_eq!((config.profile_name.as_deref(), &config.profile_source), (Some("dev"), &Source::Cli));
+        assert_eq!(config.profile.xhost.as_deref(), Some("db-dev"));
+        // dev no guarda el puerto: no se mezcla con el de prod
+        assert_eq!(source(&config, "port"), &Source::Default);
+
+        let config = resolve(&store, None, &Profile::default(), &[("RDO_PROFILE", "dev")]);
+        assert_eq!(config.profile_source, Source::Env(PROFILE_ENV));
+        assert_eq!(config.profile.xhost.as_deref(), Some("db-dev"));
+
+        assert!(matches!(Config::resolve_with(&store, Some("nope"), &Profile::default(), &|_| None), Err(RdoError::Config(_))));
+    }
+
+    #[test]
+    fn explain_reports_each_source_and_hides_the_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir);
+        let cli = Profile { xhost: Some("db-cli".into()), password: Some("s3cr3t".into()), ..Profile::default() };
+        let report = resolve(&store, None, &cli, &[("RDO_PORT", "6000")]).report();
+
+        assert!(report.starts_with("Perfil: prod (predeterminado)\n"), "{}", report);
+        let line = |field: &str| report.lines().find(|l| l.starts_with(&format!("{} ", field))).unwrap().to_string();
+        assert!(line("xhost").contains("db-cli") && line("xhost").ends_with("línea de comandos"));
+        assert!(line("port").ends_with("entorno (RDO_PORT)"));
+        assert!(line("username").ends_with("perfil 'prod'"));
+        assert!(line("dir_backup").ends_with("valor por defecto"));
+        assert!(line("data_dir").ends_with("sin definir"));
+        assert!(!report.contains("s3cr3t"));
+        assert!(line("password").contains("********"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::error::RdoError;
+use crate::output::{self, Verbosity};
+use crate::profile::Profile;
+
+/// Prefijo de los selectores por etiqueta: `label:clave=valor[,clave=valor...]`
+const LABEL_PREFIX: &str = "label:";
+
+/// Etiquetas con que docker compose (y podman-compose y nerdctl compose) marcan sus contenedores
+pub const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+pub const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// Forma en que un perfil nombra su contenedor
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerTarget {
+    /// ID o nombre: se pasa tal cual al runtime
+    Direct(String),
+    /// Servicio de un proyecto de compose: `proyecto/servicio`
+    Compose { project: String, service: String },
+    /// Etiquetas que debe tener el contenedor: `label:clave=valor,...`
+    Labels(Vec<String>),
+}
+
+impl ContainerTarget {
+    pub fn parse(value: &str) -> Result<Self, RdoError> {
+        if let Some(labels) = value.strip_prefix(LABEL_PREFIX) {
+            let labels: Vec<String> = labels.split(',').map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
+            if labels.is_empty() {
+                return Err(RdoError::Config(format!("Selector de contenedor sin etiquetas: '{}'. Use label:clave=valor", value)));
+            }
+            return Ok(ContainerTarget::Labels(labels));
+        }
+
+        // Los nombres de contenedor no pueden llevar '/', así que no hay ambigüedad
+        match value.split_once('/') {
+            Some((project, service)) if !project.is_empty() && !service.is_empty() && !service.contains('/') => {
+                Ok(ContainerTarget::Compose { project: project.to_string(), service: service.to_string() })
+            }
+            Some(_) => Err(RdoError::Config(format!("Contenedor inválido '{}': use <proyecto>/<servicio> de compose", value))),
+            None => Ok(ContainerTarget::Direct(value.to_string())),
+        }
+    }
+
+    // Filtros de `ps` que identifican el contenedor; ninguno si se usa tal cual
+    fn filters(&self) -> Vec<String> {
+        match self {
+            ContainerTarget::Direct(_) => Vec::new(),
+            ContainerTarget::Compose { project, service } => vec![
+                format!("label={}={}", COMPOSE_PROJECT_LABEL, project),
+                format!("label={}={}", COMPOSE_SERVICE_LABEL, service),
+            ],
+            ContainerTarget::Labels(labels) => labels.iter().map(|l| format!("label={}", l)).collect(),
+        }
+    }
+}
+
+impl fmt::Display for ContainerTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerTarget::Direct(id) => write!(f, "{}", id),
+            ContainerTarget::Compose { project, service } => write!(f, "{}/{}", project, service),
+            ContainerTarget::Labels(labels) => write!(f, "{}{}", LABEL_PREFIX, labels.join(",")),
+        }
+    }
+}
+
+impl Profile {
+    // Sustituir los selectores de contenedor (compose o etiquetas) por el ID del contenedor
+    // que está en marcha ahora; los IDs y nombres se dejan como están
+    pub fn resolve_containers(&mut self) -> Result<(), RdoError> {
+        if let Some(target) = self.container_id.clone() {
+            self.container_id = Some(self.resolve_container(&target)?);
+        }
+        if let Some(target) = self.odoo_container.clone() {
+            self.odoo_container = Some(self.resolve_container(&target)?);
+        }
+        Ok(())
+    }
+
+    fn resolve_container(&self, value: &str) -> Result<String, RdoError> {
+        let target = ContainerTarget::parse(value)?;
+        let filters = target.filters();
+        if filters.is_empty() {
+            return Ok(value.to_string());
+        }
+        // En modo local no hay contenedores que buscar
+        let Some(mut ps) = self.executor().ps(&filters) else {
+            return Ok(value.to_string());
+        };
+
+        let output = ps.output()?;
+        if !output.status.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let matches: Vec<(&str, &str)> = stdout.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.split_once('\t').unwrap_or((l, "")))
+            .collect();
+
+        match matches.as_slice() {
+            [] => Err(RdoError::MissingContainer(format!(
+                "Ningún contenedor en marcha coincide con '{}' (filtros: {})",
+                target, filters.join(" ")
+            ))),
+            [(id, name)] => {
+                if output::verbosity() != Verbosity::Quiet {
+                    eprintln!("Contenedor {}: {} ({})", target, name, id);
+                }
+                Ok(id.to_string())
+            }
+            several => {
+                let found: Vec<String> = several.iter().map(|(id, name)| format!("{} ({})", name, id)).collect();
+                Err(RdoError::Config(format!(
+                    "Varios contenedores en marcha coinciden con '{}': {}. Precise el selector",
+                    target, found.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{FakeRuntime, Runtime};
+    use std::sync::Arc;
+
+    fn profile(fake: &Arc<FakeRuntime>, container: &str) -> Profile {
+        Profile {
+            container_id: Some(container.into()),
+            executor: Some(fake.clone()),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn parses_the_three_forms() {
+        assert_eq!(ContainerTarget::parse("d48eed249db5").unwrap(), ContainerTarget::Direct("d48eed249db5".into()));
+        assert_eq!(
+            ContainerTarget::parse("odoo-stack/db").unwrap(),
+            ContainerTarget::Compose { project: "odoo-stack".into(), service: "db".into() }
+        );
+        assert_eq!(
+            ContainerTarget::parse("label:app=odoo, role=db").unwrap(),
+            ContainerTarget::Labels(vec!["app=odoo".into(), "role=db".into()])
+        );
+        for invalid in ["/db", "odoo-stack/", "a/b/c", "label:", "label: , "] {
+            assert!(ContainerTarget::parse(invalid).is_err(), "se aceptó {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn ids_and_names_are_not_looked_up() {
+        let fake = FakeRuntime::new(|_| (1, String::new(), "no se esperaba ps".into()));
+        let mut p = profile(&fake, "odoo-db-1");
+        p.resolve_containers().unwrap();
+        assert_eq!(p.container_id.as_deref(), Some("odoo-db-1"));
+        assert!(fake.calls().is_empty());
+    }
+
+    #[test]
+    fn compose_service_resolves_to_the_running_container() {
+        let fake = FakeRuntime::new(|_| (0, "3f2a9c\todoo-stack-db-1\n".into(), String::new()));
+        let mut p = profile(&fake, "odoo-stack/db");
+        p.odoo_container = Some("label:app=odoo".into());
+        p.resolve_containers().unwrap();
+        assert_eq!(p.container_id.as_deref(), Some("3f2a9c"));
+        assert_eq!(p.odoo_container.as_deref(), Some("3f2a9c"));
+
+        let calls = fake.calls();
+        assert_eq!(calls[0], ["ps", "label=com.docker.compose.project=odoo-stack", "label=com.docker.compose.service=db"]);
+        assert_eq!(calls[1], ["ps", "label=app=odoo"]);
+    }
+
+    #[test]
+    fn zero_or_several_matches_are_errors() {
+        let fake = FakeRuntime::new(|_| (0, String::new(), String::new()));
+        let err = profile(&fake, "odoo-stack/db").resolve_containers().unwrap_err();
+        assert!(matches!(err, RdoError::MissingContainer(_)), "{:?}", err);
+
+        let fake = FakeRuntime::new(|_| (0, "aaa\tdb-1\nbbb\tdb-2\n".into(), String::new()));
+        let err = profile(&fake, "label:role=db").resolve_containers().unwrap_err();
+        assert!(matches!(&err, RdoError::Config(m) if m.contains("db-1 (aaa)") && m.contains("db-2 (bbb)")), "{:?}", err);
+    }
+
+    #[test]
+    fn local_runtime_keeps_the_selector() {
+        let mut p = Profile {
+            container_id: Some("odoo-stack/db".into()),
+            runtime: Some(Runtime::Local),
+            ..Profile::default()
+        };
+        p.resolve_containers().unwrap();
+        assert_eq!(p.container_id.as_deref(), Some("odoo-stack/db"));
+    }
+}
@@ -0,0 +1,909 @@
+use indicatif::ProgressBar;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::process::{Command, Output, Stdio};
+
+use crate::cli::{DecompressAt, RestoreArgs};
+use crate::dump::{restore_args, Compression, Dump, DumpFormat, Location};
+use crate::error::RdoError;
+use crate::output::{self, collect, join, Stream};
+use crate::profile::Profile;
+use crate::progress;
+use crate::prompt;
+use crate::sql::{conninfo_dbname, quote_ident, quote_literal};
+
+/// Longitud máxima en bytes de un nombre de PostgreSQL (NAMEDATALEN - 1)
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+impl Profile {
+    // Verificar si una base de datos existe
+    pub fn database_exists(&self, dbname: &str) -> Result<bool, RdoError> {
+        if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
+            (&self.xhost, self.port, &self.username, self.container()) {
+            
+            // El nombre viaja como literal SQL citado dentro de un único argumento de psql
+            let sql = exists_sql(dbname);
+            let mut cmd = self.psql_command(container_id, xhost, port, username, "postgres", &sql);
+            
+            // Ejecutamos el comando
+            let output = output::run(&mut cmd)?;
+            
+            // Un fallo de psql (autenticación, contenedor...) no significa que no exista
+            if !output.status.success() {
+                return Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)));
+            }
+            
+            // Si la salida es "1", la base de datos existe
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Ok(stdout.trim() == "1")
+            
+        } else {
+            Err(RdoError::Config("Faltan datos del perfil".into()))
+        }
+    }
+    
+    // Crear una nueva base de datos
+    pub fn create_database(&self, dbname: &str) -> Result<(), RdoError> {
+        if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
+            (&self.xhost, self.port, &self.username, self.container()) {
+            
+            println!("Creando base de datos '{}'...", dbname);
+            
+            // Construimos el comando para crear la base de datos, con el nombre como identificador citado
+            let sql = create_sql(dbname);
+            let mut cmd = self.psql_command(container_id, xhost, port, username, "postgres", &sql);
+            let password_provided = self.password.is_some();
+            
+            // Ejecutamos el comando
+            let output = output::run(&mut cmd)?;
+            
+            if output.status.success() {
+                println!("Base de datos '{}' creada exitosamente", dbname);
+                Ok(())
+            } else {
+                let error = RdoError::classify(&String::from_utf8_lossy(&output.stderr));
+                
+                // Detectar si el error es por falta de contraseña
+                if error.is_auth() && !password_provided {
+                    // Pedir la contraseña (falla enseguida si no se puede preguntar)
+                    let password = prompt::password(username)?;
+
+                    // Crear una nueva instancia con la contraseña
+                    let mut new_self = self.clone();
+                    new_self.password = Some(password);
+
+                    // Intentar nuevamente con la nueva contraseña
+                    return new_self.create_database(dbname);
+                }
+
+                Err(error)
+            }
+            
+        } else {
+            Err(RdoError::Config("Faltan datos del perfil".into()))
+        }
+    }
+    
+    // Carpeta del backup de una base de datos
+    pub fn backup_folder(&self, namedb: &str) -> Result<String, RdoError> {
+        check_db_name(namedb)?;
+
+        // Usamos la ruta base del perfil, o una predeterminada si no está definida
+        // NOTA: Esta ruta es DENTRO del contenedor Docker
+        let base_dir = self.dir_backup.as_deref()
+            .unwrap_or("/tmp/backups");
+
+        Ok(format!("{}/{}", base_dir, namedb))
+    }
+
+    // Restaurar el dump del backup `backup` (su carpeta o, con plantilla de ruta, sus dumps)
+    // en la base de datos `namedb` con psql o pg_restore dentro del contenedor
+    pub fn execute_psql(&self, backup: &str, namedb: &str, opts: &RestoreArgs) -> Result<(), RdoError> {
+        if let (Some(_), Some(_), Some(username), Some(container_id)) = 
+            (&self.xhost, self.port, &self.username, self.container()) {
+            
+            check_db_name(namedb)?;
+            
+            // Buscamos el dump antes de crear nada: el de --source/--zip en el host o
+            // el de la carpeta del backup en el contenedor
+            let dump = match opts.host_source() {
+                Some(path) => Dump::host(path)?,
+                None => self.locate_dump(backup)?,
+            };
+            
+            // Verificamos si la base de datos existe; sin contraseña, se pide una vez
+            // y se repite toda la restauración con ella
+            let exists = match self.database_exists(namedb) {
+                Err(e) if e.is_auth() && self.password.is_none() => {
+                    // Pedir la contraseña (falla enseguida si no se puede preguntar)
+                    let password = prompt::password(username)?;
+                    
+                    // Crear una nueva instancia con la contraseña
+                    let mut new_self = self.clone();
+                    new_self.password = Some(password);
+                    
+                    // Intentar nuevamente con la nueva contraseña
+                    return new_self.execute_psql(backup, namedb, opts);
+                }
+                result => result?,
+            };
+            
+            // Con --replace o --rename-existing se restaura en una base de datos vacía;
+            // sin ellas, cargar un dump completo sobre los objetos existentes falla en cada uno.
+            // --clean lo evita, pero solo con pg_restore: psql lo ignora
+            if exists && opts.replace {
+                self.replace_database(namedb)?;
+            } else if exists && opts.rename_existing {
+                self.rename_existing_database(namedb)?;
+            } else if exists && !(opts.clean && dump.uses_pg_restore()) {
+                println!("Aviso: la base de datos '{}' ya existe y se restaurará sobre su contenido; use --replace o --rename-existing para empezar con una vacía", namedb);
+            }
+            // Los reintentos encuentran la base de datos ya vaciada y no deben repetirlo; el
+            // filestore se sustituye más tarde, al copiar el del zip
+            let opts = &RestoreArgs {
+                replace: false,
+                rename_existing: false,
+                replace_filestore: opts.replace_filestore || opts.replace,
+                ..opts.clone()
+            };
+            
+            if !exists {
+                println!("La base de datos '{}' no existe.", namedb);
+                
+                if !prompt::confirm_create("¿Desea crearla?")? {
+                    return Err(RdoError::MissingDatabase(format!("Operación cancelada. La base de datos '{}' no existe.", namedb)));
+                }
+                self.create_database(namedb)?;
+                println!("Base de datos creada correctamente.");
+            }
+            
+            // Si la restauración falla por autenticación, solo se pide la contraseña si no la había
+            let password_provided = self.password.is_some();
+            
+            let output = match dump.format {
+                // Backup de Odoo: dump.sql por la entrada estándar y filestore a data_dir
+                _ if dump.compression == Compression::Zip => {
+                    println!("Backup ZIP de Odoo encontrado: {}", dump.path);
+                    self.restore_odoo_zip(container_id, &dump, namedb, opts)?
+                }
+                // Dump del host: se envía por la entrada estándar sin copiarlo al contenedor
+                _ if dump.location == Location::Host => {
+                    match dump.format {
+                        Some(format) => println!("Dump del host: {} (formato {})", dump.path, format),
+                        None => println!("Dump del host: {} (comprimido con {}, se descomprime en el host)", dump.path, dump.compression),
+                    }
+                    self.restore_host_file(container_id, &dump, namedb, opts)?
+                }
+                // psql o pg_restore leen el archivo directamente en el contenedor
+                Some(format) if dump.compression == Compression::None => {
+                    println!("Dump encontrado: {} (formato {}, se restaura con {})", dump.path, format, format.tool());
+                    self.restore_file(container_id, &dump, format, namedb, opts)?
+                }
+                // Los comprimidos pasan por una tubería hacia la entrada estándar, sin escribirse expandidos
+                _ => {
+                    println!(
+                        "Dump encontrado: {} (comprimido con {}, se descomprime en el {})",
+                        dump.path, dump.compression, opts.decompress
+                    );
+                    self.restore_stream(container_id, &dump, namedb, opts)?
+                }
+            };
+            
+            if output.status.success() {
+                println!("Comando ejecutado con éxito");
+                if let Some(path) = output::log_path() {
+                    println!("Salida completa en {}", path.display());
+                }
+            } else {
+                // Solo se conservan el primer error y las últimas líneas; la salida completa está en el registro
+                eprintln!("Error al ejecutar el comando:");
+                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+                if let Some(path) = output::log_path() {
+                    eprintln!("Salida completa en {}", path.display());
+                }
+                
+                // Clasificamos el error por su SQLSTATE o, si no lo hay, por el mensaje de libpq
+                let error = RdoError::classify(&String::from_utf8_lossy(&output.stderr));
+                
+                if error.is_auth() {
+                    if !password_provided {
+                        // Pedir la contraseña (falla enseguida si no se puede preguntar)
+                        let password = prompt::password(username)?;
+                        
+                        // Crear una nueva instancia con la contraseña
+                        let mut new_self = self.clone();
+                        new_self.password = Some(password);
+                        
+                        // Intentar nuevamente con la nueva contraseña
+                        return new_self.execute_psql(backup, namedb, opts);
+                    }
+                } else if error.is_missing_database() {
+                    if prompt::confirm_create(&format!("¿Desea crear la base de datos '{}' y reintentar?", namedb))? {
+                        // Crear la base de datos y reintentar
+                        self.create_database(namedb)?;
+                        return self.execute_psql(backup, namedb, opts);
+                    } else {
+                        return Err(RdoError::MissingDatabase("Operación cancelada".into()));
+                    }
+                }
+                
+                // Si llegamos aquí, no fue ninguno de los errores recuperables
+                return Err(error);
+            }
+            
+            Ok(())
+        } else {
+            Err(RdoError::Config("Faltan datos del perfil".into()))
+        }
+    }
+
+    // Restaurar un dump leído del contenedor sin escribir nunca el archivo expandido: el contenido
+    // descomprimido pasa por una tubería hasta la entrada estándar de psql o pg_restore
+    fn restore_stream(&self, container_id: &str, dump: &Dump, namedb: &str, opts: &RestoreArgs) -> Result<Output, RdoError> {
+        let (argv, decompress_in_container) = source_argv(dump, opts.decompress);
+        let mut source = self.exec(container_id, &argv, false);
+        let mut source = source.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let source_stderr = collect(source.stderr.take());
+        let raw = source.stdout.take().ok_or("No se pudo leer el dump del contenedor")?;
+        
+        // La barra mide los bytes del archivo; si se descomprime en el contenedor, el total no se conoce
+        let total = match decompress_in_container {
+            true => None,
+            false => self.container_output(container_id, &["stat", "-c", "%s", &dump.path]).ok()
+                .and_then(|size| size.trim().parse().ok()),
+        };
+        let bar = progress::bytes_bar(total);
+        let raw = bar.wrap_read(raw);
+        
+        let input: Box<dyn Read> = match opts.decompress {
+            DecompressAt::Container => Box::new(raw),
+            DecompressAt::Host => dump.compression.decoder(raw)?,
+        };
+        
+        let result = self.pipe_into_restore(container_id, input, &bar, &dump.path, namedb, opts);
+        if result.is_err() {
+            let _ = source.kill();
+        }
+        let source_status = source.wait()?;
+        let mut output = result?;
+        
+        // Si el origen falló (por ejemplo, el descompresor no está instalado en el contenedor),
+        // la restauración tampoco es válida aunque psql haya terminado bien
+        if !source_status.success() {
+            output.stderr.extend(join(source_stderr));
+            if output.status.success() {
+                output.status = source_status;
+            }
+        }
+        
+        Ok(output)
+    }
+
+    // Restaurar un dump del host por la entrada estándar de `docker exec -i`
+    fn restore_host_file(&self, container_id: &str, dump: &Dump, namedb: &str, opts: &RestoreArgs) -> Result<Output, RdoError> {
+        if opts.decompress == DecompressAt::Container && dump.compression != Compression::None {
+            eprintln!("Aviso: los dumps del host siempre se descomprimen en el host; se ignora --decompress container");
+        }
+        
+        let file = File::open(&dump.path)
+            .map_err(|e| format!("No se pudo abrir {}: {}", dump.path, e))?;
+        let bar = progress::bytes_bar(Some(file.metadata()?.len()));
+        let input = dump.compression.decoder(BufReader::new(bar.wrap_read(file)))?;
+        self.pipe_into_restore(container_id, input, &bar, &dump.path, namedb, opts)
+    }
+
+    // psql -f o pg_restore leen el dump sin comprimir directamente en el contenedor, sin que pase
+    // por el host. Con pg_restore (admite --jobs), `pg_restore -l` da el número de elementos del
+    // TOC y `--verbose` anuncia cada uno al restaurarlo; psql no informa de su avance
+    fn restore_file(&self, container_id: &str, dump: &Dump, format: DumpFormat, namedb: &str, opts: &RestoreArgs) -> Result<Output, RdoError> {
+        let (Some(xhost), Some(port), Some(username)) = (&self.xhost, self.port, &self.username) else {
+            return Err(RdoError::Config("Faltan datos del perfil".into()));
+        };
+        
+        let mut args = restore_args(format, Some(&dump.path), xhost, port, username, namedb, opts);
+        println!("Ejecutando en {}:", self.describe(container_id));
+        println!("{}", args.join(" "));
+        
+        let bar = match format {
+            DumpFormat::Plain => progress::spinner(),
+            _ => {
+                args.insert(1, "--verbose".to_string());
+                let total = self.container_output(container_id, &["pg_restore", "-l", &dump.path]).ok()
+                    .map(|toc| toc.lines().filter(|l| !l.is_empty() && !l.starts_with(';')).count() as u64);
+                progress::entries_bar(total)
+            }
+        };
+        progress::start(&bar);
+        
+        let mut restore = self.pg_command(container_id, &args, false);
+        output::log_command(&restore);
+        let mut child = restore
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = output::watch(child.stdout.take(), Stream::Stdout, bar.clone(), opts.tail);
+        let stderr = output::watch(child.stderr.take(), Stream::Stderr, bar.clone(), opts.tail);
+        
+        let status = child.wait()?;
+        progress::finish(&bar);
+        Ok(Output { status, stdout: join(stdout), stderr: join(stderr) })
+    }
+
+    // Enviar un dump ya descomprimido a la entrada estándar de psql o pg_restore
+    pub fn pipe_into_restore<R: Read>(&self, container_id: &str, mut input: R, bar: &ProgressBar, label: &str, namedb: &str, opts: &RestoreArgs) -> Result<Output, RdoError> {
+        let (Some(xhost), Some(port), Some(username)) = (&self.xhost, self.port, &self.username) else {
+            return Err(RdoError::Config("Faltan datos del perfil".into()));
+        };
+        
+        // La cabecera del contenido ya descomprimido decide entre psql y pg_restore
+        let mut header = Vec::new();
+        input.by_ref().take(512).read_to_end(&mut header)
+            .map_err(|e| format!("Error al leer el dump {}: {}", label, e))?;
+        let format = DumpFormat::detect(&header);
+        println!("Contenido en formato {}, se restaura con {}", format, format.tool());
+        
+        let args = restore_args(format, None, xhost, port, username, namedb, opts);
+        println!("Ejecutando en {}:", self.describe(container_id));
+        println!("{}", args.join(" "));
+        
+        let mut restore = self.pg_command(container_id, &args, true);
+        output::log_command(&restore);
+        let mut child = restore
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        progress::start(bar);
+        
+        // Leemos la salida en paralelo para que psql no se bloquee con la tubería llena
+        let stdout = output::watch(child.stdout.take(), Stream::Stdout, bar.clone(), opts.tail);
+        let stderr = output::watch(child.stderr.take(), Stream::Stderr, bar.clone(), opts.tail);
+        
+        let mut stdin = child.stdin.take().ok_or("No se pudo abrir la entrada de la restauración")?;
+        let mut input = header.as_slice().chain(input);
+        let mut buf = vec![0u8; 64 * 1024];
+        let read_error = loop {
+            match input.read(&mut buf) {
+                Ok(0) => break None,
+                // Si la herramienta termina antes de tiempo, su stderr explicará el motivo
+                Ok(n) => if stdin.write_all(&buf[..n]).is_err() {
+                    break None;
+                },
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => break Some(e),
+            }
+        };
+        drop(stdin);
+        
+        if let Some(e) = read_error {
+            // Un dump dañado no debe dejar una restauración parcial que parezca correcta
+            bar.abandon();
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Error al leer el dump {}: {}", label, e).into());
+        }
+        
+        let status = child.wait()?;
+        progress::finish(bar);
+        Ok(Output { status, stdout: join(stdout), stderr: join(stderr) })
+    }
+
+    // Eliminar una base de datos
+    pub fn drop_database(&self, dbname: &str) -> Result<(), RdoError> {
+        if let (Some(xhost), Some(port), Some(username), Some(container_id)) =
+            (&self.xhost, self.port, &self.username, self.container()) {
+
+            println!("Eliminando base de datos '{}'...", dbname);
+
+            let sql = drop_sql(dbname);
+            let output = output::run(&mut self.psql_command(container_id, xhost, port, username, "postgres", &sql))?;
+
+            if output.status.success() {
+                println!("Base de datos '{}' eliminada", dbname);
+                Ok(())
+            } else {
+                Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)))
+            }
+        } else {
+            Err(RdoError::Config("Faltan datos del perfil".into()))
+        }
+    }
+
+    // --replace: tras escribir su nombre para confirmarlo, la base de datos se elimina y se crea vacía
+    fn replace_database(&self, dbname: &str) -> Result<(), RdoError> {
+        println!("La base de datos '{}' ya existe; --replace la eliminará con todos sus datos.", dbname);
+        if !prompt::confirm_typed(&format!("Escriba '{}' para confirmar", dbname), dbname)? {
+            return Err("Operación cancelada: el nombre escrito no coincide".into());
+        }
+        self.recreate_database(dbname)
+    }
+
+    // Eliminar la base de datos y crearla vacía, cerrando antes las conexiones activas
+    fn recreate_database(&self, dbname: &str) -> Result<(), RdoError> {
+        self.disconnect(dbname)?;
+        if let Err(e) = self.drop_database(dbname) {
+            self.allow_connections(dbname);
+            return Err(e);
+        }
+        self.create_database(dbname)
+    }
+
+    // --rename-existing: la base de datos actual se conserva con un nombre con fecha y hora
+    // y se restaura en una nueva con el nombre original
+    fn rename_existing_database(&self, dbname: &str) -> Result<(), RdoError> {
+        let renamed = format!("{}_{}", dbname, chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        // PostgreSQL recortaría en silencio un nombre más largo
+        if renamed.len() > MAX_IDENTIFIER_LEN {
+            return Err(RdoError::Config(format!(
+                "No se puede conservar '{}': el nombre '{}' supera los {} bytes que admite PostgreSQL",
+                dbname, renamed, MAX_IDENTIFIER_LEN
+            )));
+        }
+        if self.database_exists(&renamed)? {
+            return Err(RdoError::Config(format!("Ya existe la base de datos '{}'; vuelva a intentarlo en un segundo", renamed)));
+        }
+
+        println!("La base de datos '{}' ya existe; se conserva como '{}'", dbname, renamed);
+        self.disconnect(dbname)?;
+        if let Err(e) = self.rename_database(dbname, &renamed) {
+            self.allow_connections(dbname);
+            return Err(e);
+        }
+        self.allow_connections(&renamed);
+        // La copia conservada se queda también con sus adjuntos
+        self.rename_filestore(dbname, &renamed)?;
+        self.create_database(dbname)
+    }
+
+    pub fn rename_database(&self, from: &str, to: &str) -> Result<(), RdoError> {
+        let container_id = self.container().ok_or_else(|| RdoError::Config("Faltan datos del perfil".into()))?;
+        self.psql_query(container_id, "postgres", &rename_sql(from, to))?;
+        println!("Base de datos '{}' renombrada a '{}'", from, to);
+        Ok(())
+    }
+
+    // Cerrar las conexiones de otros clientes con pg_terminate_backend. Antes se impiden las
+    // nuevas, para que Odoo no vuelva a conectarse entre el cierre y el DROP o el RENAME
+    fn disconnect(&self, dbname: &str) -> Result<(), RdoError> {
+        let container_id = self.container().ok_or_else(|| RdoError::Config("Faltan datos del perfil".into()))?;
+        self.psql_query(container_id, "postgres", &allow_connections_sql(dbname, false))?;
+        let terminated = match self.psql_query(container_id, "postgres", &terminate_sql(dbname)) {
+            Ok(rows) => rows.lines().filter(|row| *row == "t").count(),
+            Err(e) => {
+                self.allow_connections(dbname);
+                return Err(e);
+            }
+        };
+        if terminated > 0 {
+            println!("Conexiones activas cerradas en '{}': {}", dbname, terminated);
+        }
+        Ok(())
+    }
+
+    // Volver a admitir conexiones; si falla solo se avisa, para no ocultar el error original
+    fn allow_connections(&self, dbname: &str) {
+        let Some(container_id) = self.container() else { return };
+        if let Err(e) = self.psql_query(container_id, "postgres", &allow_connections_sql(dbname, true)) {
+            eprintln!("No se pudieron volver a admitir conexiones en '{}': {}", dbname, e);
+        }
+    }
+
+    // Listar las bases de datos del servidor (sin plantillas)
+    pub fn list_databases(&self) -> Result<Vec<String>, RdoError> {
+        if let (Some(xhost), Some(port), Some(username), Some(container_id)) =
+            (&self.xhost, self.port, &self.username, self.container()) {
+
+            let sql = "SELECT datname FROM pg_database WHERE NOT datistemplate ORDER BY 1";
+            let output = output::run(&mut self.psql_command(container_id, xhost, port, username, "postgres", sql))?;
+
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                Ok(stdout.lines().map(|l| l.to_string()).collect())
+            } else {
+                Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)))
+            }
+        } else {
+            Err(RdoError::Config("Faltan datos del perfil".into()))
+        }
+    }
+
+    // Ejecutar una consulta con psql y devolver las filas sin formato (columnas separadas por '|')
+    pub fn psql_query(&self, container_id: &str, dbname: &str, sql: &str) -> Result<String, RdoError> {
+        let (Some(xhost), Some(port), Some(username)) = (&self.xhost, self.port, &self.username) else {
+            return Err(RdoError::Config("Faltan datos del perfil".into()));
+        };
+        
+        let output = self.psql_command(container_id, xhost, port, username, dbname, sql).output()?;
+        
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)))
+        }
+    }
+
+    // `psql -c` en el contenedor, con cada dato en su propio argumento y sin pasar por un shell.
+    // -At: salida sin alineación ni cabeceras, una fila por línea; con VERBOSITY=verbose
+    // los errores incluyen su SQLSTATE
+    pub fn psql_command(&self, container_id: &str, xhost: &str, port: u16, username: &str, dbname: &str, sql: &str) -> Command {
+        let port = port.to_string();
+        let dbname = conninfo_dbname(dbname);
+        self.pg_command(container_id, &[
+            "psql", "--host", xhost, "--port", &port, "--username", username, "--dbname", &dbname,
+            "--set", "VERBOSITY=verbose", "-At", "-c", sql,
+        ], false)
+    }
+}
+
+// Origen de los datos en el contenedor: el descompresor con --decompress container o el
+// archivo tal cual; el segundo valor indica si se descomprime en el contenedor
+fn source_argv(dump: &Dump, decompress: DecompressAt) -> (Vec<&str>, bool) {
+    match (decompress, dump.compression.container_tool()) {
+        (DecompressAt::Container, Some(tool)) => (vec![tool, "-dc", &dump.path], true),
+        _ => (vec!["cat", &dump.path], false),
+    }
+}
+
+// Órdenes SQL con el nombre de la base de datos citado
+fn exists_sql(dbname: &str) -> String {
+    format!("SELECT 1 FROM pg_database WHERE datname = {}", quote_literal(dbname))
+}
+
+fn create_sql(dbname: &str) -> String {
+    format!("CREATE DATABASE {}", quote_ident(dbname))
+}
+
+fn drop_sql(dbname: &str) -> String {
+    format!("DROP DATABASE {}", quote_ident(dbname))
+}
+
+fn rename_sql(from: &str, to: &str) -> String {
+    format!("ALTER DATABASE {} RENAME TO {}", quote_ident(from), quote_ident(to))
+}
+
+fn allow_connections_sql(dbname: &str, allow: bool) -> String {
+    format!("ALTER DATABASE {} WITH ALLOW_CONNECTIONS {}", quote_ident(dbname), allow)
+}
+
+// Todas las sesiones en la base de datos salvo la propia
+fn terminate_sql(dbname: &str) -> String {
+    format!(
+        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = {} AND pid <> pg_backend_pid()",
+        quote_literal(dbname)
+    )
+}
+
+// El nombre de la base de datos también es el de su carpeta de backup y de su filestore,
+// así que no puede salir de ellas
+pub fn check_db_name(namedb: &str) -> Result<(), RdoError> {
+    if namedb.is_empty() || namedb == "." || namedb == ".." || namedb.contains(['/', '\0']) {
+        return Err(RdoError::Config(format!(
+            "Nombre de base de datos inválido '{}': no puede estar vacío, ser '.' o '..' ni contener '/'",
+            namedb.escape_debug()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RestoreArgs;
+    use crate::dump::restore_args;
+    use crate::runtime::FakeRuntime;
+    use crate::sql::HOSTILE_NAMES;
+    use std::sync::Arc;
+
+    const AUTH_FAILED: &str = "psql: error: connection to server at \"db\" (10.0.0.2), port 5432 failed: FATAL:  28P01: password authentication failed for user \"odoo\"\n";
+
+    // Perfil que ejecuta las órdenes con un ejecutor falso
+    fn profile(fake: &Arc<FakeRuntime>) -> Profile {
+        Profile {
+            xhost: Some("db".into()),
+            port: Some(5432),
+            username: Some("odoo".into()),
+            container_id: Some("ctr".into()),
+            dir_backup: Some("/backups".into()),
+            executor: Some(fake.clone()),
+            ..Profile::default()
+        }
+    }
+
+    fn ok(stdout: &str) -> (i32, String, String) {
+        (0, stdout.to_string(), String::new())
+    }
+
+    fn failed(stderr: &str) -> (i32, String, String) {
+        (2, String::new(), stderr.to_string())
+    }
+
+    // Los nombres hostiles que también pueden ser carpeta de backup (sin '/')
+    fn folder_names() -> impl Iterator<Item = &'static &'static str> {
+        HOSTILE_NAMES.iter().filter(|name| check_db_name(name).is_ok())
+    }
+
+    #[test]
+    fn connection_strings_in_names_reach_libpq_as_a_single_dbname() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        let p = profile(&fake);
+        for name in ["host=evil dbname=x", "postgresql://otro/db"] {
+            p.psql_query("ctr", name, "SELECT 1").unwrap();
+            let call = fake.calls().pop().unwrap();
+            let at = call.iter().position(|a| a == "--dbname").unwrap();
+            assert!(call[at + 1].starts_with("dbname='"), "{:?}", call);
+            assert_eq!(call.iter().filter(|a| a.contains("evil") || a.contains("://")).count(), 1, "{:?}", call);
+
+            let restore = restore_args(DumpFormat::Custom, None, "db", 5432, "odoo", name, &RestoreArgs::default());
+            assert_eq!(restore[..9], ["pg_restore", "--host", "db", "--port", "5432", "--username", "odoo", "--dbname", &conninfo_dbname(name)]);
+            let dump = p.dump_args(DumpFormat::Custom, None, name, None, false).unwrap();
+            assert_eq!(dump[..3], ["pg_dump", "--host", "db"]);
+            assert_eq!(dump[dump.len() - 2..], ["--dbname".to_string(), conninfo_dbname(name)]);
+        }
+    }
+
+    // Ninguna orden pasa por un shell: el programa del contenedor es la herramienta directamente
+    fn assert_no_shell(args: &[String]) {
+        for shell in ["sh", "bash"] {
+            assert!(!args.iter().any(|a| a == shell), "se usa {} en {:?}", shell, args);
+        }
+    }
+
+    #[test]
+    fn database_exists_sends_the_name_as_a_quoted_literal() {
+        let fake = FakeRuntime::new(|_| ok("1\n"));
+        let p = profile(&fake);
+        for name in HOSTILE_NAMES {
+            assert!(p.database_exists(name).unwrap());
+        }
+
+        let calls = fake.calls();
+        assert_eq!(calls.len(), HOSTILE_NAMES.len());
+        for (call, name) in calls.iter().zip(HOSTILE_NAMES) {
+            assert_no_shell(call);
+            assert_eq!(&call[..2], ["ctr", "psql"]);
+            assert_eq!(call.last(), Some(&exists_sql(name)));
+        }
+    }
+
+    #[test]
+    fn database_exists_is_false_without_rows() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        assert!(!profile(&fake).database_exists("nueva").unwrap());
+    }
+
+    #[test]
+    fn create_and_drop_send_quoted_identifiers() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        let p = profile(&fake);
+        for name in HOSTILE_NAMES {
+            p.create_database(name).unwrap();
+            p.drop_database(name).unwrap();
+        }
+
+        let calls = fake.calls();
+        for (pair, name) in calls.chunks(2).zip(HOSTILE_NAMES) {
+            assert_no_shell(&pair[0]);
+            assert_no_shell(&pair[1]);
+            assert_eq!(pair[0].last(), Some(&create_sql(name)));
+            assert_eq!(pair[1].last(), Some(&drop_sql(name)));
+        }
+    }
+
+    #[test]
+    fn replace_blocks_and_terminates_connections_before_dropping() {
+        let fake = FakeRuntime::new(|_| ok("t\nt\n"));
+        let p = profile(&fake);
+        for name in HOSTILE_NAMES {
+            p.recreate_database(name).unwrap();
+        }
+
+        let calls = fake.calls();
+        for (steps, name) in calls.chunks(4).zip(HOSTILE_NAMES) {
+            steps.iter().for_each(|call| assert_no_shell(call));
+            let sql: Vec<_> = steps.iter().map(|call| call.last().unwrap().clone()).collect();
+            assert_eq!(sql, [allow_connections_sql(name, false), terminate_sql(name), drop_sql(name), create_sql(name)]);
+        }
+    }
+
+    #[test]
+    fn rename_existing_keeps_the_old_database_under_a_timestamped_name() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        profile(&fake).rename_existing_database("prod").unwrap();
+
+        let sql: Vec<_> = fake.calls().iter().map(|call| call.last().unwrap().clone()).collect();
+        let renamed = sql[3].strip_prefix("ALTER DATABASE \"prod\" RENAME TO \"").unwrap().trim_end_matches('"').to_string();
+        assert!(renamed.starts_with("prod_") && renamed.len() == "prod_20250428_153000".len(), "{}", renamed);
+        assert_eq!(sql, [
+            exists_sql(&renamed),
+            allow_connections_sql("prod", false),
+            terminate_sql("prod"),
+            rename_sql("prod", &renamed),
+            allow_connections_sql(&renamed, true),
+            create_sql("prod"),
+        ]);
+    }
+
+    #[test]
+    fn failed_drop_allows_connections_again() {
+        let fake = FakeRuntime::new(|args| {
+            if args.last().is_some_and(|sql| sql.starts_with("DROP")) {
+                failed("ERROR:  42501: must be owner of database prod\n")
+            } else {
+                ok("")
+            }
+        });
+        assert!(matches!(profile(&fake).recreate_database("prod"), Err(RdoError::PermissionDenied(_))));
+        assert_eq!(fake.calls().last().unwrap().last(), Some(&allow_connections_sql("prod", true)));
+    }
+
+    #[test]
+    fn compressed_dumps_are_decompressed_where_asked() {
+        let dump = |compression| Dump { path: "/b/prod/dump.sql.zst".into(), location: Location::Container, format: None, compression };
+
+        let zstd = dump(Compression::Zstd);
+        assert_eq!(source_argv(&zstd, DecompressAt::Container), (vec!["zstd", "-dc", "/b/prod/dump.sql.zst"], true));
+        assert_eq!(source_argv(&zstd, DecompressAt::Host), (vec!["cat", "/b/prod/dump.sql.zst"], false));
+        for (compression, tool) in [(Compression::Gzip, "gzip"), (Compression::Xz, "xz"), (Compression::Bzip2, "bzip2")] {
+            assert_eq!(source_argv(&dump(compression), DecompressAt::Container).0[0], tool);
+        }
+        // Sin compresión no hay nada que descomprimir en el contenedor
+        assert_eq!(source_argv(&dump(Compression::None), DecompressAt::Container), (vec!["cat", "/b/prod/dump.sql.zst"], false));
+    }
+
+    #[test]
+    fn sql_quotes_hostile_names() {
+        assert_eq!(rename_sql("a\"b", "c"), "ALTER DATABASE \"a\"\"b\" RENAME TO \"c\"");
+        assert_eq!(
+            terminate_sql("x'; --"),
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = 'x''; --' AND pid <> pg_backend_pid()"
+        );
+        assert_eq!(create_sql("x\"; DROP DATABASE postgres; --"), "CREATE DATABASE \"x\"\"; DROP DATABASE postgres; --\"");
+        assert_eq!(drop_sql("a\"b"), "DROP DATABASE \"a\"\"b\"");
+        assert_eq!(exists_sql("x'; DROP DATABASE postgres; --"), "SELECT 1 FROM pg_database WHERE datname = 'x''; DROP DATABASE postgres; --'");
+    }
+
+    #[test]
+    fn list_databases_reads_one_name_per_line() {
+        let fake = FakeRuntime::new(|_| ok("odoo\npostgres\ncon espacio\n"));
+        assert_eq!(profile(&fake).list_databases().unwrap(), ["odoo", "postgres", "con espacio"]);
+    }
+
+    #[test]
+    fn psql_query_passes_the_database_as_an_option_value() {
+        let fake = FakeRuntime::new(|_| ok("15.4\n"));
+        let p = profile(&fake);
+        for name in HOSTILE_NAMES {
+            p.psql_query("ctr", name, "SHOW server_version").unwrap();
+        }
+        for (call, name) in fake.calls().iter().zip(HOSTILE_NAMES) {
+            let at = call.iter().position(|a| a == "--dbname").unwrap();
+            assert_eq!(call[at + 1], conninfo_dbname(name));
+        }
+    }
+
+    #[test]
+    fn password_travels_as_environment_not_in_sql() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        let mut p = profile(&fake);
+        p.password = Some("pa ss'; rm -rf /".into());
+        p.create_database("x").unwrap();
+
+        let call = &fake.calls()[0];
+        assert_eq!(&call[..3], ["ctr", "PGPASSWORD=pa ss'; rm -rf /", "psql"]);
+        assert_no_shell(call);
+    }
+
+    #[test]
+    fn errors_are_classified_from_stderr() {
+        let fake = FakeRuntime::new(|_| failed(AUTH_FAILED));
+        let mut p = profile(&fake);
+        p.password = Some("mala".into());
+        assert!(matches!(p.database_exists("odoo"), Err(RdoError::Auth(_))));
+        // Con contraseña no se vuelve a preguntar
+        assert!(matches!(p.create_database("odoo"), Err(RdoError::Auth(_))));
+
+        let fake = FakeRuntime::new(|_| failed("Error response from daemon: No such container: ctr\n"));
+        assert!(matches!(profile(&fake).list_databases(), Err(RdoError::MissingContainer(_))));
+    }
+
+    #[test]
+    fn missing_password_fails_fast_without_a_terminal() {
+        // Sin contraseña, create_database la pediría; --no-input hace que falle aunque haya terminal
+        prompt::init(prompt::Answers { no_input: true, ..Default::default() });
+        let fake = FakeRuntime::new(|_| failed(AUTH_FAILED));
+        assert!(matches!(profile(&fake).create_database("odoo"), Err(RdoError::InputRequired(_))));
+        assert_eq!(fake.calls().len(), 1);
+    }
+
+    #[test]
+    fn locate_dump_lists_hostile_folders_as_single_arguments() {
+        let fake = FakeRuntime::new(|argv| match argv[0] {
+            "ls" => ok("dump.sql\nnotas.txt\n"),
+            "head" => ok("--\n-- PostgreSQL database dump\n--\n"),
+            _ => failed("orden inesperada"),
+        });
+        let p = profile(&fake);
+        for name in folder_names() {
+            let dump = p.locate_dump(name).unwrap();
+            assert_eq!(dump.path, format!("/backups/{}/dump.sql", name));
+            assert_eq!(dump.format, Some(DumpFormat::Plain));
+        }
+
+        for (pair, name) in fake.calls().chunks(2).zip(folder_names()) {
+            assert_eq!(pair[0], ["ctr", "ls", "-1Ap", &format!("/backups/{}", name)]);
+            assert_eq!(pair[1], ["ctr", "head", "-c", "512", &format!("/backups/{}/dump.sql", name)]);
+        }
+    }
+
+    #[test]
+    fn plain_restores_stop_at_the_first_sql_error() {
+        let fake = FakeRuntime::new(|argv| match argv[0] {
+            "ls" => ok("dump.sql\n"),
+            "head" => ok("--\n-- PostgreSQL database dump\n--\nSELECT * FROM falta;\n"),
+            "psql" if argv.contains(&"ON_ERROR_STOP=1") => failed(
+                "psql:<stdin>:4: ERROR:  42P01: relation \"falta\" does not exist\nLINE 1: SELECT * FROM falta;\n"
+            ),
+            "psql" => ok("1\n"),
+            _ => failed("orden inesperada"),
+        });
+        let p = profile(&fake);
+        let result = p.execute_psql("prod", "prod", &RestoreArgs::default());
+        assert!(matches!(&result, Err(RdoError::Sql { sqlstate: Some(code), .. }) if code == "42P01"), "{:?}", result);
+        assert!(fake.calls().iter().any(|call| call.contains(&"ON_ERROR_STOP=1".to_string())));
+    }
+
+    #[test]
+    fn uncompressed_sql_dumps_are_read_by_psql_inside_the_container() {
+        let fake = FakeRuntime::new(|argv| match argv[0] {
+            "ls" => ok("dump.sql\n"),
+            "head" => ok("--\n-- PostgreSQL database dump\n--\n"),
+            "psql" => ok("1\n"),
+            _ => failed("orden inesperada"),
+        });
+        profile(&fake).execute_psql("prod", "prod", &RestoreArgs::default()).unwrap();
+
+        // Ni `cat` ni `stat`: el archivo no pasa por el host
+        let calls = fake.calls();
+        assert_eq!(calls.iter().map(|call| call[1].as_str()).collect::<Vec<_>>(), ["ls", "head", "psql", "psql"]);
+        assert_eq!(calls[3][calls[3].len() - 2..], ["-f", "/backups/prod/dump.sql"]);
+    }
+
+    #[test]
+    fn locate_dump_reports_a_missing_folder() {
+        let fake = FakeRuntime::new(|_| failed("ls: cannot access '/backups/x': No such file or directory\n"));
+        assert!(matches!(profile(&fake).locate_dump("x"), Err(RdoError::MissingDump(_))));
+    }
+
+    #[test]
+    fn dump_and_restore_args_pass_names_as_option_values() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        let p = profile(&fake);
+        let opts = RestoreArgs::default();
+        for name in folder_names() {
+            let folder = p.backup_folder(name).unwrap();
+            assert_eq!(folder, format!("/backups/{}", name));
+
+            for format in [DumpFormat::Plain, DumpFormat::Custom, DumpFormat::Directory, DumpFormat::Tar] {
+                let dump = p.dump_args(format, Some(&folder), name, None, false).unwrap();
+                assert_eq!(dump[dump.len() - 2..], ["--dbname".to_string(), conninfo_dbname(name)]);
+
+                let path = format!("{}/{}", folder, format.file_name());
+                let restore = restore_args(format, Some(&path), "db", 5432, "odoo", name, &opts);
+                let at = restore.iter().position(|a| a == "--dbname").unwrap();
+                assert_eq!(restore[at + 1], conninfo_dbname(name));
+                assert!(restore.contains(&path));
+                assert_no_shell(&restore);
+            }
+        }
+    }
+
+    #[test]
+    fn backup_folder_rejects_names_that_leave_the_folder() {
+        let fake = FakeRuntime::new(|_| ok(""));
+        let p = profile(&fake);
+        for name in ["", ".", "..", "../etc", "a/b", "/etc", "nul\0byte"] {
+            assert!(matches!(p.backup_folder(name), Err(RdoError::Config(_))), "se aceptó {:?}", name);
+        }
+        assert!(fake.calls().is_empty());
+    }
+}
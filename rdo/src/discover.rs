@@ -0,0 +1,322 @@
+use serde_json::Value;
+use std::fmt;
+
+use crate::container::{COMPOSE_PROJECT_LABEL, COMPOSE_SERVICE_LABEL};
+use crate::error::RdoError;
+use crate::profile::{Profile, ProfileStore};
+use crate::prompt;
+use crate::runtime::Runtime;
+
+/// Puerto de PostgreSQL y fragmentos de imagen que delatan un contenedor de PostgreSQL u Odoo
+const POSTGRES_PORT: &str = "5432/tcp";
+const POSTGRES_IMAGES: &[&str] = &["postgres", "postgis", "timescale"];
+const ODOO_IMAGE: &str = "odoo";
+
+/// data_dir de la imagen oficial de Odoo
+const ODOO_DATA_DIR: &str = "/var/lib/odoo";
+
+/// Qué se encontró en un contenedor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Postgres,
+    Odoo,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Postgres => write!(f, "PostgreSQL"),
+            Kind::Odoo => write!(f, "Odoo"),
+        }
+    }
+}
+
+/// Contenedor en marcha que parece de PostgreSQL u Odoo, con el perfil que se propone para él
+#[derive(Debug, Clone)]
+pub struct Discovered {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub kind: Kind,
+    /// Nombre propuesto para el perfil: el servicio de compose o el nombre del contenedor
+    pub suggested_name: String,
+    pub profile: Profile,
+}
+
+// Buscar contenedores de PostgreSQL u Odoo, mostrarlos y ofrecer guardar uno como perfil
+pub fn discover(store: &mut ProfileStore, base: &Profile, save: Option<usize>, name: Option<String>) -> Result<(), RdoError> {
+    let runtime = base.runtime.unwrap_or_default();
+    let found = base.discover_containers()?;
+    if found.is_empty() {
+        println!("No hay contenedores en marcha que parezcan de PostgreSQL u Odoo ({}).", runtime);
+        return Ok(());
+    }
+
+    println!("Contenedores encontrados ({}):", runtime);
+    for (index, item) in found.iter().enumerate() {
+        let p = &item.profile;
+        println!(
+            "{:>3}  {}  {}  [{}]  contenedor={}  {}@{}:{}  contraseña: {}",
+            index + 1, item.name, item.image, item.kind,
+            p.container_id.as_deref().unwrap_or("-"),
+            p.username.as_deref().unwrap_or("-"), p.xhost.as_deref().unwrap_or("-"), p.port.unwrap_or_default(),
+            if p.password.is_some() { "sí" } else { "no" },
+        );
+    }
+
+    // Sin --save solo se pregunta si se puede; si no, basta con el listado
+    let choice = match save {
+        Some(n) => n,
+        None if prompt::can_ask() => {
+            let answer = prompt::input("Número del contenedor para guardar como perfil (Enter para no guardar): ")?;
+            if answer.is_empty() {
+                return Ok(());
+            }
+            answer.parse().map_err(|_| RdoError::Config(format!("Número inválido: '{}'", answer)))?
+        }
+        None => return Ok(()),
+    };
+    let item = choice.checked_sub(1).and_then(|i| found.get(i))
+        .ok_or_else(|| RdoError::Config(format!("No hay ningún contenedor con el número {}", choice)))?;
+
+    let name = match name {
+        Some(name) => name,
+        None if save.is_none() => {
+            let answer = prompt::input(&format!("Nombre del perfil [{}]: ", item.suggested_name))?;
+            if answer.is_empty() { item.suggested_name.clone() } else { answer }
+        }
+        None => item.suggested_name.clone(),
+    };
+
+    if store.get(&name).is_some() && !prompt::confirm(&format!("El perfil '{}' ya existe. ¿Reemplazarlo?", name))? {
+        return Err("Operación cancelada".into());
+    }
+    store.upsert(&name, item.profile.clone())?;
+    store.save()?;
+    println!("Perfil '{}' guardado en {}", name, store.path().display());
+    item.profile.print_json();
+    Ok(())
+}
+
+impl Profile {
+    // Contenedores en marcha con aspecto de PostgreSQL u Odoo, según `inspect`
+    pub fn discover_containers(&self) -> Result<Vec<Discovered>, RdoError> {
+        let executor = self.executor();
+        let runtime = self.runtime.unwrap_or_default();
+        let Some(mut ps) = executor.ps(&[]) else {
+            return Err(RdoError::Config("rdo discover necesita un runtime de contenedores (docker, podman o nerdctl)".into()));
+        };
+
+        let output = ps.output()?;
+        if !output.status.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)));
+        }
+        let listing = String::from_utf8_lossy(&output.stdout);
+        let ids: Vec<&str> = listing.lines().filter_map(|l| l.split('\t').next()).filter(|id| !id.is_empty()).collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut inspect = executor.inspect(&ids).ok_or("El runtime no permite inspeccionar contenedores")?;
+        let output = inspect.output()?;
+        if !output.status.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)));
+        }
+        let containers: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+        let stored_runtime = (runtime != Runtime::default()).then_some(runtime);
+        Ok(classify_containers(&containers, stored_runtime))
+    }
+}
+
+// Elegir los contenedores de PostgreSQL u Odoo de la salida de `inspect` y proponer un perfil para cada uno
+fn classify_containers(containers: &[Value], runtime: Option<Runtime>) -> Vec<Discovered> {
+    let mut found: Vec<Discovered> = containers.iter().filter_map(|c| classify(c, runtime)).collect();
+
+    // Un PostgreSQL con un Odoo en el mismo proyecto de compose puede restaurar también el filestore
+    let odoo_by_project: Vec<(String, String)> = containers.iter()
+        .filter(|c| kind(c) == Some(Kind::Odoo))
+        .filter_map(|c| Some((label(c, COMPOSE_PROJECT_LABEL)?.to_string(), target(c))))
+        .collect();
+    for item in found.iter_mut().filter(|i| i.kind == Kind::Postgres) {
+        let project = containers.iter()
+            .find(|c| str_at(c, &["Id"]) == Some(item.id.as_str()))
+            .and_then(|c| label(c, COMPOSE_PROJECT_LABEL));
+        if let Some((_, odoo)) = odoo_by_project.iter().find(|(p, _)| Some(p.as_str()) == project) {
+            item.profile.odoo_container = Some(odoo.clone());
+            item.profile.data_dir = Some(ODOO_DATA_DIR.to_string());
+        }
+    }
+    found
+}
+
+fn classify(container: &Value, runtime: Option<Runtime>) -> Option<Discovered> {
+    let kind = kind(container)?;
+    let env = env(container);
+    let var = |names: &[&str]| names.iter().find_map(|n| env.iter().find(|(k, _)| k == n).map(|(_, v)| v.clone()));
+    let port = |names: &[&str]| var(names).and_then(|p| p.parse().ok()).unwrap_or(5432);
+    let target = target(container);
+
+    // psql se ejecuta dentro del contenedor: en el de PostgreSQL el servidor está en localhost
+    // y en el de Odoo, en el host de la base de datos que usa Odoo
+    let profile = match kind {
+        Kind::Postgres => Profile {
+            xhost: Some(var(&["PGHOST"]).unwrap_or_else(|| "localhost".to_string())),
+            port: Some(port(&["PGPORT"])),
+            username: Some(var(&["POSTGRES_USER", "PGUSER"]).unwrap_or_else(|| "postgres".to_string())),
+            password: var(&["POSTGRES_PASSWORD", "PGPASSWORD"]),
+            container_id: Some(target.clone()),
+            runtime,
+            ..Profile::default()
+        },
+        Kind::Odoo => Profile {
+            xhost: Some(var(&["HOST", "PGHOST", "DB_HOST"]).unwrap_or_else(|| "db".to_string())),
+            port: Some(port(&["PORT", "PGPORT", "DB_PORT"])),
+            username: Some(var(&["USER", "PGUSER", "DB_USER"]).unwrap_or_else(|| "odoo".to_string())),
+            password: var(&["PASSWORD", "PGPASSWORD", "DB_PASSWORD"]),
+            container_id: Some(target.clone()),
+            data_dir: Some(ODOO_DATA_DIR.to_string()),
+            odoo_container: Some(target.clone()),
+            runtime,
+            ..Profile::default()
+        },
+    };
+
+    let name = str_at(container, &["Name"]).unwrap_or_default().trim_start_matches('/').to_string();
+    Some(Discovered {
+        id: str_at(container, &["Id"]).unwrap_or_default().to_string(),
+        suggested_name: label(container, COMPOSE_SERVICE_LABEL).map(|s| s.to_string()).unwrap_or_else(|| name.clone()),
+        name,
+        image: str_at(container, &["Config", "Image"]).unwrap_or_default().to_string(),
+        kind,
+        profile,
+    })
+}
+
+// PostgreSQL u Odoo según la imagen o el puerto 5432 expuesto
+fn kind(container: &Value) -> Option<Kind> {
+    let image = str_at(container, &["Config", "Image"]).unwrap_or_default().to_lowercase();
+    if image.contains(ODOO_IMAGE) {
+        return Some(Kind::Odoo);
+    }
+    let exposes_postgres = [&["Config", "ExposedPorts"][..], &["NetworkSettings", "Ports"][..]].iter()
+        .any(|path| at(container, path).and_then(Value::as_object).is_some_and(|ports| ports.contains_key(POSTGRES_PORT)));
+    if POSTGRES_IMAGES.iter().any(|i| image.contains(i)) || exposes_postgres {
+        return Some(Kind::Postgres);
+    }
+    None
+}
+
+// Cómo guardar el contenedor en el perfil: `proyecto/servicio` si es de compose
+// (sobrevive a que se recree) o su nombre
+fn target(container: &Value) -> String {
+    match (label(container, COMPOSE_PROJECT_LABEL), label(container, COMPOSE_SERVICE_LABEL)) {
+        (Some(project), Some(service)) => format!("{}/{}", project, service),
+        _ => str_at(container, &["Name"]).unwrap_or_default().trim_start_matches('/').to_string(),
+    }
+}
+
+// Variables de entorno del contenedor como pares (nombre, valor)
+fn env(container: &Value) -> Vec<(String, String)> {
+    at(container, &["Config", "Env"]).and_then(Value::as_array).into_iter().flatten()
+        .filter_map(Value::as_str)
+        .filter_map(|e| e.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn label<'a>(container: &'a Value, name: &str) -> Option<&'a str> {
+    at(container, &["Config", "Labels", name]).and_then(Value::as_str)
+}
+
+fn str_at<'a>(container: &'a Value, path: &[&str]) -> Option<&'a str> {
+    at(container, path).and_then(Value::as_str)
+}
+
+fn at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, key| v.get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::FakeRuntime;
+    use serde_json::json;
+
+    fn containers() -> Vec<Value> {
+        vec![
+            json!({
+                "Id": "aaa111",
+                "Name": "/odoo-stack-db-1",
+                "Config": {
+                    "Image": "postgres:15",
+                    "Env": ["POSTGRES_USER=odoo", "POSTGRES_PASSWORD=s3cr=to", "PGDATA=/var/lib/postgresql/data"],
+                    "ExposedPorts": {"5432/tcp": {}},
+                    "Labels": {"com.docker.compose.project": "odoo-stack", "com.docker.compose.service": "db"}
+                }
+            }),
+            json!({
+                "Id": "bbb222",
+                "Name": "/odoo-stack-web-1",
+                "Config": {
+                    "Image": "odoo:17",
+                    "Env": ["HOST=db", "USER=odoo", "PASSWORD=s3cr=to"],
+                    "Labels": {"com.docker.compose.project": "odoo-stack", "com.docker.compose.service": "web"}
+                }
+            }),
+            json!({
+                "Id": "ccc333",
+                "Name": "/redis",
+                "Config": {"Image": "redis:7", "ExposedPorts": {"6379/tcp": {}}}
+            }),
+            json!({
+                "Id": "ddd444",
+                "Name": "/custom-pg",
+                "Config": {"Image": "registry.local/base:1", "Env": ["PGUSER=admin", "PGPORT=5433"]},
+                "NetworkSettings": {"Ports": {"5432/tcp": null}}
+            }),
+        ]
+    }
+
+    #[test]
+    fn finds_postgres_and_odoo_by_image_or_port() {
+        let found = classify_containers(&containers(), None);
+        let kinds: Vec<(&str, Kind)> = found.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+        assert_eq!(kinds, [("odoo-stack-db-1", Kind::Postgres), ("odoo-stack-web-1", Kind::Odoo), ("custom-pg", Kind::Postgres)]);
+    }
+
+    #[test]
+    fn proposes_profiles_from_the_container_environment() {
+        let found = classify_containers(&containers(), None);
+
+        let db = &found[0].profile;
+        assert_eq!(found[0].suggested_name, "db");
+        assert_eq!(db.container_id.as_deref(), Some("odoo-stack/db"));
+        assert_eq!((db.xhost.as_deref(), db.port), (Some("localhost"), Some(5432)));
+        assert_eq!(db.username.as_deref(), Some("odoo"));
+        assert_eq!(db.password.as_deref(), Some("s3cr=to"));
+        // El Odoo del mismo proyecto aporta el filestore
+        assert_eq!(db.odoo_container.as_deref(), Some("odoo-stack/web"));
+        assert_eq!(db.data_dir.as_deref(), Some(ODOO_DATA_DIR));
+
+        let web = &found[1].profile;
+        assert_eq!((web.xhost.as_deref(), web.username.as_deref()), (Some("db"), Some("odoo")));
+        assert_eq!(web.odoo_container.as_deref(), Some("odoo-stack/web"));
+
+        let custom = &found[2].profile;
+        assert_eq!(custom.container_id.as_deref(), Some("custom-pg"));
+        assert_eq!((custom.username.as_deref(), custom.port, custom.password.as_deref()), (Some("admin"), Some(5433), None));
+    }
+
+    #[test]
+    fn inspects_every_running_container_once() {
+        let inspect = serde_json::to_string(&containers()).unwrap();
+        let fake = FakeRuntime::new(move |argv| match argv[0] {
+            "ps" => (0, "aaa111\todoo-stack-db-1\nbbb222\todoo-stack-web-1\n".into(), String::new()),
+            _ => (0, inspect.clone(), String::new()),
+        });
+        let base = Profile { executor: Some(fake.clone()), ..Profile::default() };
+
+        assert_eq!(base.discover_containers().unwrap().len(), 3);
+        assert_eq!(fake.calls(), [vec!["ps"], vec!["inspect", "aaa111", "bbb222"]]);
+    }
+}
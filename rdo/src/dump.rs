@@ -0,0 +1,595 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::cli::RestoreArgs;
+use crate::error::RdoError;
+use crate::profile::Profile;
+use crate::sql::conninfo_dbname;
+
+/// Nombres de dump que se buscan dentro de la carpeta del backup, por orden de preferencia
+pub const DUMP_CANDIDATES: &[&str] = &[
+    "dump.sql", "dump.dump", "dump.tar", "dump",
+    "dump.sql.gz", "dump.sql.zst", "dump.sql.xz", "dump.sql.bz2",
+    "dump.tar.gz", "dump.tar.zst", "dump.tar.xz", "dump.tar.bz2",
+];
+
+/// Cabecera de los dumps en formato custom (`pg_dump -Fc`)
+const CUSTOM_MAGIC: &[u8] = b"PGDMP";
+
+/// Posición y contenido de la marca de un archivo tar (`pg_dump -Ft`)
+pub const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// Índice que pg_dump escribe en los dumps en formato directorio (`pg_dump -Fd`)
+const DIRECTORY_TOC: &str = "toc.dat";
+
+/// Formato de un dump de pg_dump
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpFormat {
+    /// SQL en texto plano, se restaura con psql
+    Plain,
+    /// `pg_dump -Fc`
+    Custom,
+    /// `pg_dump -Fd`
+    Directory,
+    /// `pg_dump -Ft`
+    Tar,
+}
+
+impl DumpFormat {
+    // Detectar el formato a partir de los primeros bytes del archivo
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(CUSTOM_MAGIC) {
+            DumpFormat::Custom
+        } else if header.len() >= TAR_MAGIC_OFFSET + TAR_MAGIC.len()
+            && &header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()] == TAR_MAGIC {
+            DumpFormat::Tar
+        } else {
+            DumpFormat::Plain
+        }
+    }
+
+    // Herramienta que restaura este formato
+    pub fn tool(&self) -> &'static str {
+        match self {
+            DumpFormat::Plain => "psql",
+            _ => "pg_restore",
+        }
+    }
+
+    // Valor de `pg_dump --format`
+    pub fn pg_dump_flag(&self) -> &'static str {
+        match self {
+            DumpFormat::Plain => "p",
+            DumpFormat::Custom => "c",
+            DumpFormat::Directory => "d",
+            DumpFormat::Tar => "t",
+        }
+    }
+
+    // Formato de un dump comprimido según su nombre (dump.sql.gz, prod.tar.zst...),
+    // sin descomprimirlo
+    pub fn from_name(name: &str) -> Option<Self> {
+        let stem = [".gz", ".zst", ".xz", ".bz2"].iter()
+            .find_map(|ext| name.strip_suffix(ext))
+            .unwrap_or(name);
+        [DumpFormat::Plain, DumpFormat::Custom, DumpFormat::Tar, DumpFormat::Directory].into_iter()
+            .find(|format| stem == format.file_name() || stem.rsplit_once('.').is_some_and(|(_, ext)| ext == format.extension()))
+    }
+
+    // Nombre del dump en la carpeta del backup, uno de DUMP_CANDIDATES
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            DumpFormat::Plain => "dump.sql",
+            DumpFormat::Custom => "dump.dump",
+            DumpFormat::Directory => "dump",
+            DumpFormat::Tar => "dump.tar",
+        }
+    }
+
+    // Valor de {ext} en las plantillas de ruta, sin la compresión
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DumpFormat::Plain => "sql",
+            DumpFormat::Custom => "dump",
+            DumpFormat::Directory => "dir",
+            DumpFormat::Tar => "tar",
+        }
+    }
+}
+
+impl fmt::Display for DumpFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DumpFormat::Plain => "texto plano",
+            DumpFormat::Custom => "custom",
+            DumpFormat::Directory => "directorio",
+            DumpFormat::Tar => "tar",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Compresión de un dump, detectada por sus primeros bytes
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+    /// Backup ZIP del gestor de bases de datos de Odoo (dump.sql + filestore + manifest.json)
+    #[value(skip)]
+    Zip,
+}
+
+impl Compression {
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else if header.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if header.starts_with(b"PK\x03\x04") {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+
+    // Programa que descomprime a la salida estándar dentro del contenedor (`<tool> -dc <archivo>`)
+    pub fn container_tool(&self) -> Option<&'static str> {
+        match self {
+            Compression::None | Compression::Zip => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Zstd => Some("zstd"),
+            Compression::Xz => Some("xz"),
+            Compression::Bzip2 => Some("bzip2"),
+        }
+    }
+
+    // Extensión que se añade al nombre del dump
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+            Compression::Xz => ".xz",
+            Compression::Bzip2 => ".bz2",
+            Compression::Zip => ".zip",
+        }
+    }
+
+    // Comprimir todo el lector en el escritor, cerrando el flujo comprimido al terminar
+    pub fn encode<R: Read, W: Write>(&self, mut reader: R, writer: W) -> io::Result<u64> {
+        match self {
+            Compression::None => {
+                let mut writer = writer;
+                io::copy(&mut reader, &mut writer)
+            }
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+                let n = io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?;
+                Ok(n)
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(writer, 0)?;
+                let n = io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?;
+                Ok(n)
+            }
+            Compression::Xz => {
+                let mut encoder = liblzma::write::XzEncoder::new(writer, 6);
+                let n = io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?;
+                Ok(n)
+            }
+            Compression::Bzip2 => {
+                let mut encoder = bzip2::write::BzEncoder::new(writer, bzip2::Compression::default());
+                let n = io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?;
+                Ok(n)
+            }
+            Compression::Zip => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "un zip de Odoo no se puede generar como flujo",
+            )),
+        }
+    }
+
+    // Envolver un lector con el descompresor correspondiente (descompresión en el host)
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Compression::Xz => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            // Un zip necesita acceso aleatorio; se lee con ZipArchive
+            Compression::Zip => return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "un zip de Odoo no se puede descomprimir como flujo",
+            )),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => write!(f, "sin compresión"),
+            Compression::Zip => write!(f, "zip de Odoo"),
+            _ => write!(f, "{}", self.container_tool().unwrap_or_default()),
+        }
+    }
+}
+
+/// Sistema de archivos donde está un dump
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location {
+    Container,
+    Host,
+}
+
+/// Dump localizado en el contenedor o en el host
+#[derive(Debug, Clone)]
+pub struct Dump {
+    pub path: String,
+    pub location: Location,
+    /// Formato del contenido; en los dumps comprimidos se conoce al descomprimir
+    pub format: Option<DumpFormat>,
+    pub compression: Compression,
+}
+
+// Argumentos de psql o pg_restore para restaurar un dump de este formato.
+// Sin `path`, la herramienta lee el dump de la entrada estándar.
+pub fn restore_args(format: DumpFormat, path: Option<&str>, xhost: &str, port: u16, username: &str, namedb: &str, opts: &RestoreArgs) -> Vec<String> {
+    let mut args = vec![
+        format.tool().to_string(),
+        "--host".to_string(), xhost.to_string(),
+        "--port".to_string(), port.to_string(),
+        "--username".to_string(), username.to_string(),
+        "--dbname".to_string(), conninfo_dbname(namedb),
+    ];
+
+    let ignored = ignored_options(format, path, opts);
+    if let Some(warning) = ignored {
+        eprintln!("Aviso: {}", warning);
+    }
+
+    if format == DumpFormat::Plain {
+        // Sin ON_ERROR_STOP, psql sigue tras un error de SQL y termina con 0; los errores
+        // incluyen su SQLSTATE, para clasificarlos
+        args.extend(["--set".to_string(), "ON_ERROR_STOP=1".to_string()]);
+        args.extend(["--set".to_string(), "VERBOSITY=verbose".to_string()]);
+        if let Some(path) = path {
+            args.extend(["-f".to_string(), path.to_string()]);
+        }
+        return args;
+    }
+
+    if let Some(jobs) = opts.jobs.filter(|_| ignored.is_none()) {
+        args.extend(["--jobs".to_string(), jobs.to_string()]);
+    }
+    if opts.no_owner {
+        args.push("--no-owner".to_string());
+    }
+    if opts.clean {
+        args.extend(["--clean".to_string(), "--if-exists".to_string()]);
+    }
+    if let Some(path) = path {
+        args.push(path.to_string());
+    }
+    args
+}
+
+// Aviso por las opciones que no se pueden aplicar a este formato o a la entrada estándar
+fn ignored_options(format: DumpFormat, path: Option<&str>, opts: &RestoreArgs) -> Option<&'static str> {
+    if format == DumpFormat::Plain {
+        (opts.jobs.is_some() || opts.no_owner || opts.clean)
+            .then_some("--jobs, --no-owner y --clean solo se aplican a pg_restore; se ignoran con dumps en texto plano")
+    } else if opts.jobs.is_some() && (format == DumpFormat::Tar || path.is_none()) {
+        // pg_restore no admite restauración en paralelo desde un archivo tar ni desde la entrada estándar
+        Some("--jobs solo se admite con dumps custom o directorio sin comprimir; se ignora")
+    } else {
+        None
+    }
+}
+
+impl Dump {
+    // Dump en el sistema de archivos del host (--source host:<ruta> o --zip)
+    pub fn host(path: &Path) -> Result<Self, RdoError> {
+        let display = path.display().to_string();
+        let metadata = fs::metadata(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => RdoError::MissingDump(format!("No existe {}", display)),
+            _ => RdoError::from(e),
+        })?;
+
+        // pg_restore solo lee el formato directorio desde una ruta, nunca por la entrada estándar
+        if metadata.is_dir() {
+            return Err(format!(
+                "{} es un directorio: un dump en formato directorio no se puede enviar al contenedor; use un dump custom, tar o SQL",
+                display
+            ).into());
+        }
+
+        let mut header = Vec::new();
+        File::open(path)?.take(512).read_to_end(&mut header)?;
+        let (format, compression) = Dump::detect(&header);
+        Ok(Dump { path: display, location: Location::Host, format, compression })
+    }
+
+    // Si se restaura con pg_restore, el único que aplica --clean. De un comprimido se juzga
+    // por su nombre; un zip de Odoo siempre lleva dump.sql
+    pub fn uses_pg_restore(&self) -> bool {
+        let file = self.path.rsplit('/').next().unwrap_or(&self.path);
+        self.compression != Compression::Zip
+            && self.format.or_else(|| DumpFormat::from_name(file)).is_some_and(|format| format != DumpFormat::Plain)
+    }
+
+    // Compresión y, si no está comprimido, formato a partir de los primeros bytes
+    pub fn detect(header: &[u8]) -> (Option<DumpFormat>, Compression) {
+        let compression = Compression::detect(header);
+        let format = match compression {
+            Compression::None => Some(DumpFormat::detect(header)),
+            _ => None,
+        };
+        (format, compression)
+    }
+}
+
+impl Profile {
+    // Buscar el dump de un backup dentro del contenedor y detectar su formato. Con plantilla
+    // de ruta, `namedb` también puede ser la ruta de uno de sus dumps
+    pub fn locate_dump(&self, namedb: &str) -> Result<Dump, RdoError> {
+        if let Some(template) = self.path_template()? {
+            return self.locate_template_dump(&template, namedb);
+        }
+        let container_id = self.container().ok_or("Falta ID del contenedor")?;
+        let folder = self.backup_folder(namedb)?;
+
+        // Listamos la carpeta una sola vez; `-p` marca los directorios con '/'
+        let entries = self.container_output(container_id, &["ls", "-1Ap", &folder]).map_err(|e| match e {
+            RdoError::Other(m) => RdoError::MissingDump(format!("No se pudo leer la carpeta de backup {}: {}", folder, m)),
+            e => e,
+        })?;
+        let entries: Vec<&str> = entries.lines().collect();
+
+        // Un dump en formato directorio se reconoce por su toc.dat
+        let candidate = choose_dump(&entries, |dir| {
+            let listing = self.container_output(container_id, &["ls", "-1A", &format!("{}/{}", folder, dir)])?;
+            Ok(listing.lines().any(|l| l == DIRECTORY_TOC))
+        })?;
+
+        let Some(candidate) = candidate else {
+            return Err(RdoError::MissingDump(format!(
+                "No se encontró ningún dump en {} (se buscó: {} o un *.zip de Odoo)",
+                folder, DUMP_CANDIDATES.join(", ")
+            )));
+        };
+        let path = format!("{}/{}", folder, candidate.name);
+        let (format, compression) = match candidate.kind {
+            CandidateKind::Directory => (Some(DumpFormat::Directory), Compression::None),
+            CandidateKind::OdooZip => (None, Compression::Zip),
+            CandidateKind::File => {
+                let header = self.container_bytes(container_id, &["head", "-c", "512", &path])?;
+                Dump::detect(&header)
+            }
+        };
+        Ok(Dump { path, location: Location::Container, format, compression })
+    }
+
+    // Ejecutar un comando en el contenedor y devolver su salida estándar en bruto
+    pub fn container_bytes(&self, container_id: &str, argv: &[&str]) -> Result<Vec<u8>, RdoError> {
+        let output = self.exec(container_id, argv, false).output()?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(RdoError::classify(&String::from_utf8_lossy(&output.stderr)))
+        }
+    }
+
+    // Igual que container_bytes, pero como texto
+    pub fn container_output(&self, container_id: &str, argv: &[&str]) -> Result<String, RdoError> {
+        let stdout = self.container_bytes(container_id, argv)?;
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+}
+
+/// Tipo de dump elegido en una carpeta de backup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateKind {
+    /// Archivo de DUMP_CANDIDATES
+    File,
+    /// Directorio de `pg_dump -Fd` (con toc.dat)
+    Directory,
+    /// Zip de Odoo con nombre variable
+    OdooZip,
+}
+
+/// Dump elegido en el listado de una carpeta
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate<'a> {
+    pub name: &'a str,
+    pub kind: CandidateKind,
+}
+
+// Elegir el dump de una carpeta listada como `ls -1Ap` (directorios con '/' al final):
+// el primero de DUMP_CANDIDATES y, si no hay, un *.zip de Odoo. Los directorios solo
+// cuentan si `is_directory_dump` confirma que son un dump en formato directorio
+pub fn choose_dump<'a>(
+    entries: &[&'a str],
+    mut is_directory_dump: impl FnMut(&str) -> Result<bool, RdoError>,
+) -> Result<Option<Candidate<'a>>, RdoError> {
+    for candidate in DUMP_CANDIDATES {
+        if let Some(dir) = entries.iter().find(|e| e.strip_suffix('/') == Some(candidate)) {
+            let name = dir.trim_end_matches('/');
+            if is_directory_dump(name)? {
+                return Ok(Some(Candidate { name, kind: CandidateKind::Directory }));
+            }
+            continue;
+        }
+        if let Some(name) = entries.iter().find(|e| *e == candidate) {
+            return Ok(Some(Candidate { name, kind: CandidateKind::File }));
+        }
+    }
+
+    // Los backups descargados de Odoo tienen nombres variables: <db>_<fecha>.zip
+    Ok(entries.iter()
+        .find(|e| e.ends_with(".zip"))
+        .map(|name| Candidate { name, kind: CandidateKind::OdooZip }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cabecera de un archivo tar: la marca "ustar" en la posición 257
+    fn tar_header() -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..9].copy_from_slice(b"toc.dat\0\0");
+        header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 6].copy_from_slice(b"ustar\0");
+        header
+    }
+
+    #[test]
+    fn formats_are_detected_from_their_headers() {
+        assert_eq!(DumpFormat::detect(b"PGDMP\x01\x0e\x00\x04\x08\x01\x01"), DumpFormat::Custom);
+        assert_eq!(DumpFormat::detect(&tar_header()), DumpFormat::Tar);
+        assert_eq!(DumpFormat::detect(b"--\n-- PostgreSQL database dump\n--\n"), DumpFormat::Plain);
+        // Una cabecera corta o vacía no llega a la marca tar
+        assert_eq!(DumpFormat::detect(&tar_header()[..TAR_MAGIC_OFFSET + 2]), DumpFormat::Plain);
+        assert_eq!(DumpFormat::detect(b""), DumpFormat::Plain);
+
+        assert_eq!(Dump::detect(b"PGDMP\x01"), (Some(DumpFormat::Custom), Compression::None));
+        // El formato de un dump comprimido solo se conoce al descomprimirlo
+        assert_eq!(Dump::detect(&[0x1f, 0x8b, 0x08, 0x00]), (None, Compression::Gzip));
+    }
+
+    #[test]
+    fn directory_dumps_need_their_toc() {
+        let listings = |dir: &str| -> Result<bool, RdoError> {
+            let listing: &[&str] = match dir {
+                "dump" => &["toc.dat", "3001.dat.gz"],
+                _ => &[],
+            };
+            Ok(listing.contains(&DIRECTORY_TOC))
+        };
+        let chosen = choose_dump(&["notas.txt", "dump/"], listings).unwrap().unwrap();
+        assert_eq!(chosen, Candidate { name: "dump", kind: CandidateKind::Directory });
+
+        // Un directorio "dump" sin toc.dat no es un dump: se sigue buscando
+        let chosen = choose_dump(&["dump/", "dump.sql.gz"], |_| Ok(false)).unwrap().unwrap();
+        assert_eq!(chosen, Candidate { name: "dump.sql.gz", kind: CandidateKind::File });
+        assert_eq!(choose_dump(&["dump/"], |_| Ok(false)).unwrap(), None);
+
+        // El orden de DUMP_CANDIDATES decide y el zip de Odoo es el último recurso
+        let chosen = choose_dump(&["prod_2025-04-28.zip", "dump.tar", "dump.dump"], listings).unwrap().unwrap();
+        assert_eq!(chosen.name, "dump.dump");
+        let chosen = choose_dump(&["prod_2025-04-28.zip", "leeme.txt"], listings).unwrap().unwrap();
+        assert_eq!(chosen, Candidate { name: "prod_2025-04-28.zip", kind: CandidateKind::OdooZip });
+    }
+
+    const CODECS: [Compression; 4] = [Compression::Gzip, Compression::Zstd, Compression::Xz, Compression::Bzip2];
+
+    #[test]
+    fn compression_is_detected_from_magic_bytes() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+        assert_eq!(Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x24]), Compression::Zstd);
+        assert_eq!(Compression::detect(b"\xfd7zXZ\x00\x00"), Compression::Xz);
+        assert_eq!(Compression::detect(b"BZh91AY&SY"), Compression::Bzip2);
+        assert_eq!(Compression::detect(b"PK\x03\x04\x14\x00"), Compression::Zip);
+        assert_eq!(Compression::detect(b"PGDMP"), Compression::None);
+        // Un prefijo incompleto de la marca no basta
+        assert_eq!(Compression::detect(&[0x28, 0xb5]), Compression::None);
+        assert_eq!(Compression::detect(b"\xfd7zX"), Compression::None);
+    }
+
+    #[test]
+    fn compressed_formats_are_known_from_their_names() {
+        assert_eq!(DumpFormat::from_name("dump.sql.gz"), Some(DumpFormat::Plain));
+        assert_eq!(DumpFormat::from_name("dump.tar.zst"), Some(DumpFormat::Tar));
+        assert_eq!(DumpFormat::from_name("prod.dump.xz"), Some(DumpFormat::Custom));
+        assert_eq!(DumpFormat::from_name("prod.sql.bz2"), Some(DumpFormat::Plain));
+        assert_eq!(DumpFormat::from_name("dump"), Some(DumpFormat::Directory));
+        assert_eq!(DumpFormat::from_name("prod.backup.gz"), None);
+
+        // Cada dump comprimido de DUMP_CANDIDATES termina en la extensión de su compresión
+        for name in DUMP_CANDIDATES.iter().filter(|n| n.matches('.').count() == 2) {
+            assert!(CODECS.iter().any(|c| name.ends_with(c.extension())), "{}", name);
+            assert!(DumpFormat::from_name(name).is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn every_codec_round_trips_and_is_detected() {
+        let sql = "--\n-- PostgreSQL database dump\n--\nSELECT 1;\n".repeat(200);
+        for codec in CODECS {
+            let mut encoded = Vec::new();
+            assert_eq!(codec.encode(sql.as_bytes(), &mut encoded).unwrap(), sql.len() as u64);
+            assert_eq!(Compression::detect(&encoded), codec, "{}", codec);
+            assert!(encoded.len() < sql.len(), "{}", codec);
+
+            let mut decoded = String::new();
+            codec.decoder(encoded.as_slice()).unwrap().read_to_string(&mut decoded).unwrap();
+            assert_eq!(decoded, sql, "{}", codec);
+            assert_eq!(Dump::detect(&encoded), (None, codec));
+        }
+
+        // Dos flujos concatenados (por ejemplo, `cat a.gz b.gz`) se leen enteros
+        let mut twice = Vec::new();
+        Compression::Gzip.encode(&b"uno;"[..], &mut twice).unwrap();
+        Compression::Gzip.encode(&b"dos;"[..], &mut twice).unwrap();
+        let mut decoded = String::new();
+        Compression::Gzip.decoder(twice.as_slice()).unwrap().read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, "uno;dos;");
+
+        assert!(Compression::Zip.decoder(&b""[..]).is_err());
+    }
+
+    #[test]
+    fn pg_restore_options_are_ignored_where_they_do_not_apply() {
+        let jobs = RestoreArgs { jobs: Some(4), ..RestoreArgs::default() };
+        let args = |format, path| restore_args(format, path, "db", 5432, "odoo", "prod", &jobs);
+
+        // Texto plano: psql no tiene --jobs
+        assert!(ignored_options(DumpFormat::Plain, Some("/b/dump.sql"), &jobs).is_some());
+        assert!(ignored_options(DumpFormat::Plain, None, &RestoreArgs { no_owner: true, ..RestoreArgs::default() }).is_some());
+        assert!(!args(DumpFormat::Plain, Some("/b/dump.sql")).contains(&"--jobs".to_string()));
+
+        // Por la entrada estándar o desde un tar, pg_restore no restaura en paralelo
+        for (format, path) in [(DumpFormat::Custom, None), (DumpFormat::Tar, Some("/b/dump.tar"))] {
+            assert!(ignored_options(format, path, &jobs).is_some(), "{:?} {:?}", format, path);
+            assert!(!args(format, path).contains(&"--jobs".to_string()));
+        }
+
+        for format in [DumpFormat::Custom, DumpFormat::Directory] {
+            assert_eq!(ignored_options(format, Some("/b/dump"), &jobs), None);
+            assert!(args(format, Some("/b/dump")).windows(2).any(|w| w == ["--jobs", "4"]));
+        }
+        assert_eq!(ignored_options(DumpFormat::Plain, None, &RestoreArgs::default()), None);
+    }
+
+    #[test]
+    fn pg_restore_is_known_before_decompressing() {
+        let dump = |path: &str, format, compression| Dump { path: path.to_string(), location: Location::Container, format, compression };
+        assert!(dump("/b/dump.dump", Some(DumpFormat::Custom), Compression::None).uses_pg_restore());
+        assert!(dump("/b/prod.tar.zst", None, Compression::Zstd).uses_pg_restore());
+        assert!(!dump("/b/dump.sql", Some(DumpFormat::Plain), Compression::None).uses_pg_restore());
+        assert!(!dump("/b/dump.sql.gz", None, Compression::Gzip).uses_pg_restore());
+        assert!(!dump("/b/prod_2025-04-28.zip", None, Compression::Zip).uses_pg_restore());
+        // Sin un nombre conocido no se puede saber
+        assert!(!dump("/b/prod.backup.gz", None, Compression::Gzip).uses_pg_restore());
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::io;
+
+/// Errores de rdo; cada clase termina con su propio código de salida
+#[derive(Debug)]
+pub enum RdoError {
+    /// Falta la contraseña o es incorrecta (SQLSTATE 28xxx)
+    Auth(String),
+    /// La base de datos no existe (SQLSTATE 3D000)
+    MissingDatabase(String),
+    /// El contenedor no existe o no está en marcha
+    MissingContainer(String),
+    /// No se encontró el dump o la carpeta del backup
+    MissingDump(String),
+    /// Permiso denegado en PostgreSQL (SQLSTATE 42501) o en el sistema de archivos
+    PermissionDenied(String),
+    /// Sin espacio en disco (SQLSTATE 53100 o ENOSPC)
+    DiskFull(String),
+    /// Error de SQL, con su SQLSTATE si psql lo informó
+    Sql { sqlstate: Option<String>, message: String },
+    /// Hacía falta una respuesta del usuario y no se podía preguntar
+    InputRequired(String),
+    /// Perfil o configuración incompletos o inválidos
+    Config(String),
+    /// Cualquier otro error
+    Other(String),
+}
+
+impl RdoError {
+    // Código de salida del proceso; 2 queda para los errores de uso de clap
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RdoError::Other(_) => 1,
+            RdoError::InputRequired(_) => 3,
+            RdoError::Config(_) => 4,
+            RdoError::Auth(_) => 10,
+            RdoError::MissingDatabase(_) => 11,
+            RdoError::MissingContainer(_) => 12,
+            RdoError::MissingDump(_) => 13,
+            RdoError::PermissionDenied(_) => 14,
+            RdoError::DiskFull(_) => 15,
+            RdoError::Sql { .. } => 16,
+        }
+    }
+
+    // Clasificar la salida de errores de psql, pg_restore, pg_dump o docker.
+    // Manda la primera línea con SQLSTATE (psql con VERBOSITY=verbose) o con ERROR/FATAL; las
+    // siguientes solo son consecuencia de ella. Sin ninguna, se reconoce el texto de libpq o docker
+    pub fn classify(stderr: &str) -> Self {
+        let stderr = stderr.trim();
+
+        if let Some(line) = stderr.lines().find(|line| is_error_line(line)) {
+            let message = line.to_string();
+            return match sqlstate(line) {
+                Some(code) if code.starts_with("28") => RdoError::Auth(message),
+                Some("3D000") => RdoError::MissingDatabase(message),
+                Some("42501") => RdoError::PermissionDenied(message),
+                Some("53100") => RdoError::DiskFull(message),
+                Some(code) => RdoError::Sql { sqlstate: Some(code.to_string()), message },
+                // Sin VERBOSITY=verbose: el mensaje de libpq todavía puede decir qué pasó
+                None => classify_text(line).unwrap_or(RdoError::Sql { sqlstate: None, message }),
+            };
+        }
+
+        stderr.lines().find_map(classify_text)
+            .unwrap_or_else(|| RdoError::Other(stderr.to_string()))
+    }
+
+    pub fn is_auth(&self) -> bool {
+        matches!(self, RdoError::Auth(_))
+    }
+
+    pub fn is_missing_database(&self) -> bool {
+        matches!(self, RdoError::MissingDatabase(_))
+    }
+}
+
+// Mensajes de libpq y docker que no llevan SQLSTATE
+fn classify_text(line: &str) -> Option<RdoError> {
+    let lower = line.to_lowercase();
+    let message = line.to_string();
+
+    if lower.contains("fe_sendauth: no password supplied") || lower.contains("password authentication failed") {
+        Some(RdoError::Auth(message))
+    } else if lower.contains("database \"") && lower.contains("does not exist") {
+        Some(RdoError::MissingDatabase(message))
+    } else if lower.contains("no such container") || lower.contains("is not running") {
+        Some(RdoError::MissingContainer(message))
+    } else if lower.contains("no space left on device") {
+        Some(RdoError::DiskFull(message))
+    } else if lower.contains("permission denied") {
+        Some(RdoError::PermissionDenied(message))
+    } else {
+        None
+    }
+}
+
+// Línea de error de PostgreSQL: la que informa del fallo, con o sin SQLSTATE
+pub fn is_error_line(line: &str) -> bool {
+    sqlstate(line).is_some() || line.contains("ERROR:") || line.contains("FATAL:") || line.contains("PANIC:")
+}
+
+// SQLSTATE de una línea "ERROR:  42P01: ..." de psql con VERBOSITY=verbose
+fn sqlstate(line: &str) -> Option<&str> {
+    let (_, rest) = ["ERROR:", "FATAL:", "PANIC:"].iter().find_map(|level| line.split_once(level))?;
+    let code = rest.trim_start().split(':').next()?;
+    let valid = code.len() == 5 && code.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase());
+    valid.then_some(code)
+}
+
+impl fmt::Display for RdoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RdoError::Auth(m) => write!(f, "Error de autenticación: {}", m),
+            RdoError::MissingDatabase(m) => write!(f, "Base de datos inexistente: {}", m),
+            RdoError::MissingContainer(m) => write!(f, "Contenedor no disponible: {}", m),
+            RdoError::MissingDump(m) => write!(f, "Dump no encontrado: {}", m),
+            RdoError::PermissionDenied(m) => write!(f, "Permiso denegado: {}", m),
+            RdoError::DiskFull(m) => write!(f, "Sin espacio en disco: {}", m),
+            RdoError::Sql { sqlstate: Some(code), message } => write!(f, "Error de SQL ({}): {}", code, message),
+            RdoError::Sql { sqlstate: None, message } => write!(f, "Error de SQL: {}", message),
+            RdoError::InputRequired(m) | RdoError::Config(m) | RdoError::Other(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl std::error::Error for RdoError {}
+
+impl From<io::Error> for RdoError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => RdoError::PermissionDenied(e.to_string()),
+            io::ErrorKind::StorageFull => RdoError::DiskFull(e.to_string()),
+            _ => RdoError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<String> for RdoError {
+    fn from(message: String) -> Self {
+        RdoError::Other(message)
+    }
+}
+
+impl From<&str> for RdoError {
+    fn from(message: &str) -> Self {
+        RdoError::Other(message.to_string())
+    }
+}
+
+impl From<serde_json::Error> for RdoError {
+    fn from(e: serde_json::Error) -> Self {
+        RdoError::Other(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for RdoError {
+    fn from(e: zip::result::ZipError) -> Self {
+        RdoError::Other(e.to_string())
+    }
+}
+
+impl From<std::path::StripPrefixError> for RdoError {
+    fn from(e: std::path::StripPrefixError) -> Self {
+        RdoError::Other(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nombre de la variante, para comparar sin el mensaje
+    fn kind(error: &RdoError) -> &'static str {
+        match error {
+            RdoError::Auth(_) => "Auth",
+            RdoError::MissingDatabase(_) => "MissingDatabase",
+            RdoError::MissingContainer(_) => "MissingContainer",
+            RdoError::MissingDump(_) => "MissingDump",
+            RdoError::PermissionDenied(_) => "PermissionDenied",
+            RdoError::DiskFull(_) => "DiskFull",
+            RdoError::Sql { .. } => "Sql",
+            RdoError::InputRequired(_) => "InputRequired",
+            RdoError::Config(_) => "Config",
+            RdoError::Other(_) => "Other",
+        }
+    }
+
+    #[test]
+    fn stderr_is_classified_into_its_exit_code() {
+        let cases = [
+            ("psql: error: connection to server at \"db\" (10.0.0.2), port 5432 failed: FATAL:  28P01: password authentication failed for user \"odoo\"", "Auth", 10),
+            ("psql: error: connection to server at \"db\", port 5432 failed: fe_sendauth: no password supplied", "Auth", 10),
+            ("psql: error: connection to server at \"db\", port 5432 failed: FATAL:  3D000: database \"prod\" does not exist", "MissingDatabase", 11),
+            ("psql: error: connection to server at \"db\", port 5432 failed: FATAL:  database \"prod\" does not exist", "MissingDatabase", 11),
+            ("Error response from daemon: container 4f1c is not running", "MissingContainer", 12),
+            ("Error: No such container: odoo-db", "MissingContainer", 12),
+            ("ERROR:  42501: permission denied for table res_users", "PermissionDenied", 14),
+            ("ERROR:  53100: could not extend file \"base/16384/2619\": No space left on device", "DiskFull", 15),
+            ("pg_restore: error: could not write to output file: No space left on device", "DiskFull", 15),
+            ("psql:/b/dump.sql:42: ERROR:  42P01: relation \"res_partner\" does not exist", "Sql", 16),
+            ("ERROR:  syntax error at or near \"SELEC\"", "Sql", 16),
+            ("sh: 1: zstd: not found", "Other", 1),
+        ];
+        for (stderr, expected, code) in cases {
+            let error = RdoError::classify(stderr);
+            assert_eq!(kind(&error), expected, "{}", stderr);
+            assert_eq!(error.exit_code(), code, "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn the_first_error_line_decides() {
+        // El texto que sigue a un ERROR no cambia su clase aunque hable de un contenedor
+        let error = RdoError::classify("ERROR:  P0001: la tarea programada falló\nCONTEXT:  el servicio is not running\n");
+        assert!(matches!(&error, RdoError::Sql { sqlstate: Some(code), .. } if code == "P0001"), "{:?}", error);
+        let error = RdoError::classify("ERROR:  no se pudo cargar\nDETAIL:  permission denied for schema x");
+        assert_eq!(kind(&error), "Sql");
+
+        // Los avisos previos no cuentan: manda el primer ERROR
+        let error = RdoError::classify("NOTICE:  table \"x\" does not exist, skipping\nERROR:  42501: permission denied for database prod");
+        assert_eq!(kind(&error), "PermissionDenied");
+        let error = RdoError::classify("ERROR:  42P07: relation \"a\" already exists\nERROR:  28000: no pg_hba.conf entry");
+        assert!(matches!(error, RdoError::Sql { sqlstate: Some(ref code), .. } if code == "42P07"));
+    }
+
+    #[test]
+    fn exit_codes_match_the_documented_table() {
+        let message = || "m".to_string();
+        let errors = [
+            (RdoError::Other(message()), 1),
+            (RdoError::InputRequired(message()), 3),
+            (RdoError::Config(message()), 4),
+            (RdoError::Auth(message()), 10),
+            (RdoError::MissingDatabase(message()), 11),
+            (RdoError::MissingContainer(message()), 12),
+            (RdoError::MissingDump(message()), 13),
+            (RdoError::PermissionDenied(message()), 14),
+            (RdoError::DiskFull(message()), 15),
+            (RdoError::Sql { sqlstate: None, message: message() }, 16),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.exit_code(), code, "{:?}", error);
+        }
+        assert_eq!(RdoError::from(io::Error::from(io::ErrorKind::PermissionDenied)).exit_code(), 14);
+    }
+}
@@ -1,566 +1,308 @@
-use clap::Parser;
-use std::io::{Write, Read, stdin, stdout};
-use serde::{Serialize, Deserialize};
-use std::fs;
-use std::process::Command;
-use std::env;
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
+use std::process::ExitCode;
 
-/// Herramienta para restaurar bases de datos Odoo en contenedores Docker
+mod backup;
+mod backups;
+mod cli;
+mod config;
+mod container;
+mod db;
+mod discover;
+mod dump;
+mod error;
+mod odoo;
+mod output;
+mod password;
+mod picker;
+mod profile;
+mod progress;
+mod prompt;
+mod runtime;
+mod secret;
+mod sql;
+mod template;
 
-#[derive(Parser, Debug, Serialize, Deserialize, Clone)]
-#[command(version, about, long_about = None)]
-struct Args {
-    /// Host de la base de datos
-    #[arg(short, long)]
-    xhost: Option<String>,
-    
-    /// Puerto de la base de datos
-    #[arg(short, long)]
-    port: Option<u16>,
+use cli::{Args, BackupsAction, Commands, ConfigAction, ConnectionArgs, DbAction, ListArgs, ProfileAction, RestoreArgs};
+use backups::BackupInfo;
+use config::Config;
+use error::RdoError;
+use output::Verbosity;
+use prompt::Answers;
+use profile::{Profile, ProfileStore};
 
-    /// Usuario de la base de datos
-    #[arg(short, long)]
-    username: Option<String>,
-    
-    /// Contraseña de la base de datos (opcional, se puede usar PGPASSWORD)
-    #[arg(short = 'w', long)]
-    password: Option<String>,
 
-    /// ID del contenedor docker
-    #[arg(short, long)]
-    container_id: Option<String>,
-    
-    /// Ver perfil guardado
-    #[arg(long = "vp")]
-    #[serde(skip)]
-    view_profile: bool,
-
-    /// Ver backups
-    #[arg(long = "vb")]
-    #[serde(skip)]
-    view_backups: bool,
-    
-    /// Nombre de la base de datos destino
-    #[arg(short, long)]
-    namedb: Option<String>,
-    
-    /// Ruta base para backups (dentro del contenedor)
-    #[arg(short, long)]
-    dir_backup: Option<String>,
-    
-    /// Ejecutar el comando de restauración
-    #[arg(short, long)]
-    run: bool,
+// Perfil efectivo: línea de comandos, entorno, perfil guardado y valores por defecto
+fn effective_profile(store: &ProfileStore, requested: Option<&str>, conn: &ConnectionArgs) -> Result<Profile, RdoError> {
+    let mut profile = Config::resolve(store, requested, &conn.to_profile())?.profile;
+    // Los selectores de compose o de etiquetas se convierten en el ID del contenedor actual
+    profile.resolve_containers()?;
+    Ok(profile)
 }
 
-impl Args {
-    fn new(xhost: String, port: u16, username: String, password: Option<String>, 
-           container_id: String, dir_backup: Option<String>) -> Self {
-        Self { 
-            xhost: Some(xhost), 
-            port: Some(port), 
-            username: Some(username),
-            password,
-            container_id: Some(container_id),
-            dir_backup,
-            view_profile: false,
-            view_backups: false, // Campo faltante añadido aquí
-            namedb: None,
-            run: false
-        }
+// Igual que effective_profile, pero exige los datos de conexión completos
+fn connection_profile(store: &ProfileStore, requested: Option<&str>, conn: &ConnectionArgs) -> Result<Profile, RdoError> {
+    let mut profile = effective_profile(store, requested, conn)?;
+    if !profile.is_complete() {
+        return Err(RdoError::Config("Faltan datos de conexión. Use --profile, --container-id, la variable RDO_CONTAINER_ID o --runtime local".into()));
     }
-    // create and save a Profile
+    // La contraseña guardada se descifra solo cuando hace falta conectar.
+    // Sin contraseña directa se prueba password_command y ~/.pgpass antes de preguntarla
+    store.reveal(&mut profile)?;
+    profile.resolve_password()?;
+    Ok(profile)
+}
 
-    fn save(&self) -> Result<(), std::io::Error> {
-        // Solo guardar si tenemos todos los campos necesarios
-        if self.xhost.is_some() && self.port.is_some() && self.username.is_some() && self.container_id.is_some() {
-            // parse the profile to json
-            let json = serde_json::to_string(self).unwrap();
-            // save the json to a file
-            let mut file = std::fs::File::create("profile.json")?;
-            file.write_all(json.as_bytes())?;
-            println!("Profile saved to profile.json");
-        } else {
-            println!("Faltan campos necesarios para guardar el perfil.");
+fn run_profile_action(store: &mut ProfileStore, requested: Option<&str>, action: ProfileAction) -> Result<(), RdoError> {
+    match action {
+        ProfileAction::List => {
+            let mut empty = true;
+            for name in store.names() {
+                let marker = if store.default_name() == Some(name.as_str()) { "*" } else { " " };
+                println!("{} {}", marker, name);
+                empty = false;
+            }
+            if empty {
+                println!("No hay perfiles guardados en {}", store.path().display());
+            }
         }
-        Ok(())
-    }
-    
-    // Function to print the JSON to console
-    fn print_json(&self) {
-        let json = serde_json::to_string_pretty(self).unwrap();
-        println!("Profile JSON:");
-        println!("{}", json);
-    }
-    
-    // Function to load and print profile from file
-    fn load_and_print() -> Result<(), std::io::Error> {
-        // Check if profile exists
-        if !std::path::Path::new("profile.json").exists() {
-            println!("No profile found. Create one first.");
-            return Ok(());
+        ProfileAction::Show { name } => {
+            let profile = store.load(name.as_deref().or(requested))?;
+            profile.print_json();
         }
-        
-        // Read the profile file
-        let mut file = fs::File::open("profile.json")?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        // Pretty print the JSON
-        let parsed: serde_json::Value = serde_json::from_str(&contents)?;
-        let pretty = serde_json::to_string_pretty(&parsed)?;
-        println!("{}", pretty);
-        
-        Ok(())
-    }
-    
-    // Cargar perfil desde archivo
-    fn load() -> Result<Self, std::io::Error> {
-        if !std::path::Path::new("profile.json").exists() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "No profile found. Create one first."
-            ));
-        }
-        
-        let mut file = fs::File::open("profile.json")?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        let profile: Self = serde_json::from_str(&contents)?;
-        Ok(profile)
-    }
-    
-    // Verificar si una base de datos existe
-    fn database_exists(&self, dbname: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
-            (&self.xhost, self.port, &self.username, &self.container_id) {
-            
-            // Construimos un comando psql para verificar si la base de datos existe
-            let check_cmd = format!(
-                "psql --host \"{}\" --port \"{}\" --username \"{}\" --dbname postgres -c \"SELECT 1 FROM pg_database WHERE datname = '{}';\"",
-                xhost, port, username, dbname
-            );
-            
-            // Preparamos el comando docker con variables de entorno si hay contraseña
-            let mut cmd = Command::new("docker");
-            cmd.args(&["exec"]);
-            
-            // Si hay contraseña, la agregamos como variable de entorno PGPASSWORD
-            if let Some(password) = &self.password {
-                cmd.args(&["-e", &format!("PGPASSWORD={}", password)]);
+        ProfileAction::Save { name, conn } => {
+            let profile = conn.to_profile();
+            profile.check_templates()?;
+            if !profile.is_complete() {
+                return Err(RdoError::Config("Faltan campos necesarios para guardar el perfil: xhost, port, username y container_id (salvo con --runtime local)".into()));
             }
-            
-            // Completamos el comando con el ID del contenedor y el comando a ejecutar
-            cmd.args(&[container_id, "bash", "-c", &check_cmd]);
-            
-            // Ejecutamos el comando
-            let output = cmd.output()?;
-            
-            // Si el comando fue exitoso y la salida contiene un "1", la base de datos existe
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            Ok(output.status.success() && stdout.contains("(1 row)"))
-            
-        } else {
-            Err("Faltan datos del perfil".into())
+            // Guardamos en el perfil indicado, el de --profile o el predeterminado
+            let name = name.or_else(|| store.resolve_name(requested))
+                .unwrap_or_else(|| "default".to_string());
+            store.upsert(&name, profile.clone())?;
+            store.save()?;
+            println!("Perfil '{}' guardado en {}", name, store.path().display());
+            profile.print_json();
         }
-    }
-    
-    // Crear una nueva base de datos
-    fn create_database(&self, dbname: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
-            (&self.xhost, self.port, &self.username, &self.container_id) {
-            
-            println!("Creando base de datos '{}'...", dbname);
-            
-            // Construimos el comando para crear la base de datos
-            let create_cmd = format!(
-                "psql --host \"{}\" --port \"{}\" --username \"{}\" --dbname postgres -c \"CREATE DATABASE \\\"{}\\\";\"",
-                xhost, port, username, dbname
-            );
-            
-            // Preparamos el comando docker con variables de entorno si hay contraseña
-            let mut cmd = Command::new("docker");
-            cmd.args(&["exec"]);
-            
-            // Si hay contraseña, la agregamos como variable de entorno PGPASSWORD
-            let mut password_provided = false;
-            if let Some(password) = &self.password {
-                cmd.args(&["-e", &format!("PGPASSWORD={}", password)]);
-                password_provided = true;
-            }
-            
-            // Completamos el comando con el ID del contenedor y el comando a ejecutar
-            cmd.args(&[container_id, "bash", "-c", &create_cmd]);
-            
-            // Ejecutamos el comando
-            let output = cmd.output()?;
-            
-            if output.status.success() {
-                println!("Base de datos '{}' creada exitosamente", dbname);
-                Ok(())
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                // Detectar si el error es por falta de contraseña
-                if stderr.contains("fe_sendauth: no password supplied") {
-                    if !password_provided {
-                        println!("Se requiere contraseña para el usuario '{}'", username);
-                        print!("Ingrese la contraseña: ");
-                        stdout().flush()?;
-                        
-                        // Leer la contraseña desde la entrada estándar
-                        let mut password = String::new();
-                        stdin().read_line(&mut password)?;
-                        let password = password.trim().to_string();
-                        
-                        // Crear una nueva instancia con la contraseña
-                        let mut new_self = self.clone();
-                        new_self.password = Some(password);
-                        
-                        // Intentar nuevamente con la nueva contraseña
-                        return new_self.create_database(dbname);
-                    }
-                }
-                
-                Err(format!("Error al crear la base de datos: {}", stderr).into())
+        ProfileAction::Create { name, conn } => {
+            let profile = conn.to_profile();
+            profile.check_templates()?;
+            if !profile.is_complete() {
+                return Err(RdoError::Config("Faltan campos necesarios para guardar el perfil: xhost, port, username y container_id (salvo con --runtime local)".into()));
             }
-            
-        } else {
-            Err("Faltan datos del perfil".into())
+            store.create(&name, profile)?;
+            store.save()?;
+            println!("Perfil '{}' creado en {}", name, store.path().display());
         }
-    }
-    
-    // Generar ruta al archivo SQL basado en el nombre de la base de datos
-    fn generate_file_path(&self, namedb: &str) -> Result<String, Box<dyn std::error::Error>> {
-        // Usamos la ruta base del perfil, o una predeterminada si no está definida
-        // NOTA: Esta ruta es DENTRO del contenedor Docker
-        let base_dir = self.dir_backup.as_ref()
-            .map(|s| s.as_str())
-            .unwrap_or("/tmp/backups");
-        
-        // Construimos la ruta completa: dir_base/namedb/dump.sql (DENTRO del contenedor)
-        let file_path = format!("{}/{}/dump.sql", base_dir, namedb);
-        
-        Ok(file_path)
-    }
-    
-    // Ejecutar comando psql dentro del contenedor
-    fn execute_psql(&self, namedb: &str) -> Result<(), Box<dyn std::error::Error>> {
-        if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
-            (&self.xhost, self.port, &self.username, &self.container_id) {
-            
-            // Verificamos si la base de datos existe
-            if !self.database_exists(namedb)? {
-                println!("La base de datos '{}' no existe.", namedb);
-                print!("¿Desea crearla? (s/n): ");
-                stdout().flush()?;
-                
-                let mut input = String::new();
-                stdin().read_line(&mut input)?;
-                
-                if input.trim().to_lowercase() == "s" {
-                    match self.create_database(namedb) {
-                        Ok(_) => println!("Base de datos creada correctamente."),
-                        Err(e) => {
-                            // Si el error es porque no se puede crear la BD, intentamos detectar
-                            // si es porque falta la contraseña
-                            let error_msg = e.to_string();
-                            if error_msg.contains("fe_sendauth: no password supplied") {
-                                println!("Se requiere contraseña para el usuario '{}'", username);
-                                print!("Ingrese la contraseña: ");
-                                stdout().flush()?;
-                                
-                                // Leer la contraseña desde la entrada estándar
-                                let mut password = String::new();
-                                stdin().read_line(&mut password)?;
-                                let password = password.trim().to_string();
-                                
-                                // Crear una nueva instancia con la contraseña
-                                let mut new_self = self.clone();
-                                new_self.password = Some(password);
-                                
-                                // Intentar nuevamente con la nueva contraseña
-                                new_self.create_database(namedb)?;
-                            } else {
-                                return Err(e);
-                            }
-                        }
-                    }
-                } else {
-                    return Err(format!("Operación cancelada. La base de datos '{}' no existe.", namedb).into());
-                }
-            }
-            
-            // Generamos la ruta al archivo SQL basado en el nombre de la BD
-            let file_path = self.generate_file_path(namedb)?;
-            
-            // Construimos el comando psql básico
-            let psql_cmd = format!(
-                "psql --host \"{}\" --port \"{}\" --username \"{}\" --dbname \"{}\" -f \"{}\"",
-                xhost, port, username, namedb, file_path
-            );
-            
-            println!("Ejecutando en el contenedor {}:", container_id);
-            println!("{}", psql_cmd);
-            
-            // Preparamos el comando docker con variables de entorno si hay contraseña
-            let mut cmd = Command::new("docker");
-            cmd.args(&["exec"]);
-            
-            // Si hay contraseña, la agregamos como variable de entorno PGPASSWORD
-            let mut password_provided = false;
-            if let Some(password) = &self.password {
-                cmd.args(&["-e", &format!("PGPASSWORD={}", password)]);
-                password_provided = true;
-            }
-            
-            // Completamos el comando con el ID del contenedor y el comando a ejecutar
-            cmd.args(&[container_id, "bash", "-c", &psql_cmd]);
-            
-            // Ejecutamos el comando
-            let output = cmd.output()?;
-            
-            if output.status.success() {
-                println!("Comando ejecutado con éxito");
-                println!("Salida:");
-                println!("{}", String::from_utf8_lossy(&output.stdout));
-            } else {
-                eprintln!("Error al ejecutar el comando:");
-                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
-                
-                // Verificamos si el error es por base de datos no existente
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                if stderr.contains("fe_sendauth: no password supplied") {
-                    if !password_provided {
-                        println!("Se requiere contraseña para el usuario '{}'", username);
-                        print!("Ingrese la contraseña: ");
-                        stdout().flush()?;
-                        
-                        // Leer la contraseña desde la entrada estándar
-                        let mut password = String::new();
-                        stdin().read_line(&mut password)?;
-                        let password = password.trim().to_string();
-                        
-                        // Crear una nueva instancia con la contraseña
-                        let mut new_self = self.clone();
-                        new_self.password = Some(password);
-                        
-                        // Intentar nuevamente con la nueva contraseña
-                        return new_self.execute_psql(namedb);
-                    }
-                } else if stderr.contains("database") && stderr.contains("does not exist") {
-                    print!("¿Desea crear la base de datos '{}' y reintentar? (s/n): ", namedb);
-                    stdout().flush()?;
-                    
-                    let mut input = String::new();
-                    stdin().read_line(&mut input)?;
-                    
-                    if input.trim().to_lowercase() == "s" {
-                        // Crear la base de datos y reintentar
-                        self.create_database(namedb)?;
-                        return self.execute_psql(namedb);
-                    } else {
-                        return Err("Operación cancelada".into());
-                    }
-                }
-                
-                // Si llegamos aquí, no fue ninguno de los errores conocidos
-                return Err(format!("Error al ejecutar el comando: {}", stderr).into());
+        ProfileAction::Update { name, conn } => {
+            let profile = conn.to_profile();
+            profile.check_templates()?;
+            store.update(&name, &profile)?;
+            store.save()?;
+            println!("Perfil '{}' actualizado", name);
+        }
+        ProfileAction::Rename { from, to } => {
+            store.rename(&from, &to)?;
+            store.save()?;
+            println!("Perfil '{}' renombrado a '{}'", from, to);
+        }
+        ProfileAction::Delete { name } => {
+            store.delete(&name)?;
+            store.save()?;
+            println!("Perfil '{}' eliminado", name);
+        }
+        ProfileAction::Default { name } => {
+            store.set_default(&name)?;
+            store.save()?;
+            println!("Perfil predeterminado: '{}'", name);
+        }
+        ProfileAction::Unlock => {
+            // Para `eval "$(rdo profile unlock)"`: la frase de paso se pregunta en la terminal
+            println!("export {}={}", secret::SESSION_KEY_ENV, store.unlock()?);
+        }
+        ProfileAction::Rekey => {
+            let count = store.rekey()?;
+            store.save()?;
+            println!("{} contraseñas cifradas con la nueva frase de paso en {}", count, store.path().display());
+            if std::env::var_os(secret::SESSION_KEY_ENV).is_some() {
+                println!("La clave de sesión ha cambiado: ejecute de nuevo 'eval \"$(rdo profile unlock)\"'");
             }
-            
-            Ok(())
-        } else {
-            Err("Faltan datos del perfil".into())
         }
     }
+    Ok(())
+}
 
-    // Listar carpetas de backup y verificar si contienen dump.sql
-    fn view_backup_folders(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if let (Some(container_id), Some(dir_backup)) = (&self.container_id, &self.dir_backup) {
-            println!("Listando carpetas de backup en {} (dentro del contenedor {}):", dir_backup, container_id);
-            
-            // Comando para listar directorios en la ruta de backup (dentro del contenedor)
-            let list_cmd = format!("find {} -maxdepth 1 -type d -not -path {} | sort", dir_backup, dir_backup);
-            
-            // Ejecutar el comando en el contenedor
-            let mut cmd = Command::new("docker");
-            cmd.args(&["exec", container_id, "bash", "-c", &list_cmd]);
-            
-            let output = cmd.output()?;
-            
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(format!("Error al listar directorios: {}", stderr).into());
+fn run_db_action(store: &ProfileStore, requested: Option<&str>, action: DbAction) -> Result<(), RdoError> {
+    match action {
+        DbAction::Create { namedb, conn } => {
+            let profile = connection_profile(store, requested, &conn)?;
+            if profile.database_exists(&namedb)? {
+                return Err(format!("La base de datos '{}' ya existe", namedb).into());
             }
-            
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let dirs: Vec<&str> = stdout.lines().collect();
-            
-            if dirs.is_empty() {
-                println!("No se encontraron carpetas de backup.");
-                return Ok(());
+            profile.create_database(&namedb)?;
+        }
+        DbAction::Drop { namedb, conn } => {
+            let profile = connection_profile(store, requested, &conn)?;
+            if !profile.database_exists(&namedb)? {
+                return Err(RdoError::MissingDatabase(format!("La base de datos '{}' no existe", namedb)));
             }
-            
-            println!("Carpetas encontradas:");
-            
-            for dir in dirs {
-                let dir_name = dir.split('/').last().unwrap_or(dir);
-                
-                // Verificar si existe dump.sql en la carpeta
-                let check_sql_cmd = format!("[ -f {}/{}/dump.sql ] && echo 'true' || echo 'false'", dir_backup, dir_name);
-                
-                let mut check_cmd = Command::new("docker");
-                check_cmd.args(&["exec", container_id, "bash", "-c", &check_sql_cmd]); // Corrigiendo el nombre de la variable
-                
-                let check_output = check_cmd.output()?;
-                let has_dump = String::from_utf8_lossy(&check_output.stdout).trim() == "true";
-                
-                // Mostrar el resultado con una marca según si tiene dump.sql o no
-                let marker = if has_dump { "✓" } else { "X" };
-                println!("{} {} - {}", marker, dir_name, if has_dump { "Tiene dump.sql" } else { "No tiene dump.sql" });
+            if !prompt::confirm(&format!("¿Seguro que desea eliminar la base de datos '{}'?", namedb))? {
+                return Err("Operación cancelada".into());
             }
-            
-            Ok(())
-        } else {
-            if self.container_id.is_none() {
-                Err("Falta ID del contenedor. Especifique --container_id".into())
-            } else {
-                Err("Falta directorio de backup. Especifique --dir_backup".into())
+            profile.drop_database(&namedb)?;
+        }
+        DbAction::List { conn } => {
+            let profile = connection_profile(store, requested, &conn)?;
+            for name in profile.list_databases()? {
+                println!("{}", name);
             }
         }
     }
-} 
+    Ok(())
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    
-    // Si se usa la bandera --vp, cargar y mostrar el perfil guardado
+// Traducir las banderas antiguas (--vp, --vb, --run) a subcomandos
+fn legacy_command(args: Args) -> Option<Commands> {
     if args.view_profile {
-        if let Err(e) = Args::load_and_print() {
-            eprintln!("Error al cargar el perfil: {}", e);
-        }
-        return Ok(());
+        eprintln!("Aviso: --vp está obsoleto, use 'rdo profile show'");
+        return Some(Commands::Profile { action: ProfileAction::Show { name: None } });
     }
-    
-    // Si se usa la bandera --vb, listar las carpetas de backup
+
     if args.view_backups {
-        // Intentamos cargar el perfil guardado para obtener container_id y dir_backup
-        let profile = match Args::load() {
-            Ok(mut p) => {
-                // Actualizamos dir_backup si se proporcionó en línea de comandos
-                if let Some(dir_backup) = &args.dir_backup {
-                    p.dir_backup = Some(dir_backup.clone());
-                }
-                // Actualizamos container_id si se proporcionó en línea de comandos
-                if let Some(container_id) = &args.container_id {
-                    p.container_id = Some(container_id.clone());
-                }
-                p
-            },
-            Err(e) => {
-                // Si no hay perfil guardado, usamos los argumentos actuales
-                if let (Some(container_id), Some(dir_backup)) = (&args.container_id, &args.dir_backup) {
-                    let profile = Args::new(
-                        "".to_string(),  // xhost (no importa en este caso)
-                        0,              // port (no importa en este caso)
-                        "".to_string(),  // username (no importa en este caso)
-                        None,           // password (no importa en este caso)
-                        container_id.clone(),
-                        Some(dir_backup.clone())
-                    );
-                    profile
-                } else {
-                    return Err(format!("Error al cargar el perfil: {}. Especifique --container_id y --dir_backup.", e).into());
-                }
-            }
-        };
-        
-        profile.view_backup_folders()?;
-        return Ok(());
+        eprintln!("Aviso: --vb está obsoleto, use 'rdo backups list'");
+        return Some(Commands::Backups { action: BackupsAction::List { conn: args.conn, list: ListArgs::default() } });
     }
-    
-    // Si se proporciona run y namedb, ejecutar el comando psql
+
     if args.run {
-        // Intentamos cargar el perfil guardado
-        let mut profile = match Args::load() {
-            Ok(mut p) => {
-                // Actualizamos dir_backup si se proporcionó en línea de comandos
-                if let Some(dir_backup) = &args.dir_backup {
-                    p.dir_backup = Some(dir_backup.clone());
-                }
-                // Actualizamos password si se proporcionó en línea de comandos
-                if let Some(password) = &args.password {
-                    p.password = Some(password.clone());
-                }
-                p
-            },
-            Err(e) => {
-                // Si no hay perfil guardado, usamos los argumentos actuales
-                if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
-                    (&args.xhost, args.port, &args.username, &args.container_id) {
-                    Args::new(
-                        xhost.clone(), 
-                        port, 
-                        username.clone(),
-                        args.password.clone(),
-                        container_id.clone(),
-                        args.dir_backup.clone()
-                    )
-                } else {
-                    return Err(format!("Error al cargar el perfil: {}. Especifique todos los parámetros.", e).into());
-                }
+        eprintln!("Aviso: --run está obsoleto, use 'rdo restore <namedb>'");
+        // clap garantiza que --run viene con --namedb
+        let namedb = args.namedb.unwrap_or_default();
+        return Some(Commands::Restore { namedb: Some(namedb), from: None, latest: None, to: None, conn: args.conn, restore: RestoreArgs::default() });
+    }
+
+    // Sin banderas, con los datos de conexión completos, se guarda el perfil
+    if args.conn.to_profile().is_complete() {
+        eprintln!("Aviso: guardar sin subcomando está obsoleto, use 'rdo profile save'");
+        return Some(Commands::Profile { action: ProfileAction::Save { name: None, conn: args.conn } });
+    }
+
+    None
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            // Cada clase de error tiene su código para que los scripts puedan distinguirlas
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), RdoError> {
+    let mut args = Args::parse();
+    if args.command.is_some() && args.uses_legacy_flags() {
+        Args::command().error(
+            ErrorKind::ArgumentConflict,
+            "las banderas obsoletas y las opciones de conexión no se pueden combinar con un subcomando; indíquelas después del subcomando",
+        ).exit();
+    }
+    let mut store = ProfileStore::open()?;
+    let requested = args.profile.clone();
+    let requested = requested.as_deref();
+    let verbosity = Verbosity::from_flags(args.quiet, args.verbose);
+    let log_file = args.log_file.clone();
+    prompt::init(Answers { yes: args.yes, no_input: args.no_input, create_if_missing: args.create_if_missing });
+
+    let command = match args.command.take() {
+        Some(command) => command,
+        None => match legacy_command(args) {
+            Some(command) => command,
+            None => {
+                println!("Uso: rdo <COMANDO>. Ejecute 'rdo --help' para ver todos los comandos.");
+                println!("Para guardar un perfil: rdo profile save --xhost db --port 5432 --username odoo --container_id mi-contenedor --dir_backup /tmp/backups");
+                println!("Para autenticación con contraseña: ~/.pgpass, --password-command 'pass show db' o la variable PGPASSWORD");
+                println!("Para ver el perfil guardado: rdo profile show [nombre]");
+                println!("Para ver las carpetas de backup: rdo backups list [--profile nombre]");
+                println!("Para restaurar una base de datos: rdo restore mi_base_datos [--profile nombre]");
+                println!("Para crear un backup: rdo backup mi_base_datos [--format custom] [--zip]");
+                return Ok(());
             }
-        };
-        
-        // Si no hay contraseña en los argumentos o perfil, intentamos usar PGPASSWORD
-        if profile.password.is_none() {
-            if let Ok(pg_pass) = env::var("PGPASSWORD") {
-                profile.password = Some(pg_pass);
+        },
+    };
+
+    output::init(verbosity, log_file, command.name());
+
+    // Contraseñas de versiones anteriores, guardadas en claro
+    let plaintext = store.plaintext_secrets();
+    if !plaintext.is_empty() && verbosity != Verbosity::Quiet && !matches!(command, Commands::Profile { action: ProfileAction::Rekey }) {
+        eprintln!(
+            "Aviso: los perfiles {} guardan la contraseña en claro; cífrelas con 'rdo profile rekey'",
+            plaintext.join(", ")
+        );
+    }
+
+    match command {
+        Commands::Profile { action } => run_profile_action(&mut store, requested, action),
+        Commands::Backups { action: BackupsAction::List { conn, list } } => {
+            let profile = effective_profile(&store, requested, &conn)?;
+            profile.view_backup_folders(&list)
+        }
+        // Sin backup, el backup y la base de datos destino se eligen en el selector
+        Commands::Restore { namedb: None, from: None, latest: None, to, conn, restore } => {
+            let profile = connection_profile(&store, requested, &conn)?;
+            let target_template = profile.target_template()?;
+            let now = chrono::Local::now().naive_local();
+            // Destino propuesto: el de --to o el de target_template
+            let default_target = move |backup: &BackupInfo| match &to {
+                Some(to) => to.clone(),
+                None => target_template.render(&backup.name, now),
+            };
+            let Some(choice) = picker::pick_backup(profile.backup_catalog(false)?, default_target)? else {
+                println!("Restauración cancelada");
+                return Ok(());
+            };
+            println!("Restaurando el backup '{}' en la base de datos '{}'", choice.backup.name, choice.namedb);
+            // Con plantilla de ruta, un nombre puede tener varios dumps: se restaura el elegido
+            let backup = match &profile.path_template {
+                Some(_) => choice.backup.dump_path().unwrap_or_else(|| choice.backup.name.clone()),
+                None => choice.backup.name.clone(),
+            };
+            profile.execute_psql(&backup, &choice.namedb, &restore)
+        }
+        Commands::Restore { namedb, from, latest, to, conn, restore } => {
+            let profile = connection_profile(&store, requested, &conn)?;
+            // --latest elige el backup en el catálogo y sigue como --from
+            let from = match latest {
+                Some(pattern) => Some(profile.latest_backup(&pattern)?),
+                None => from,
+            };
+            let backup = namedb.or_else(|| from.clone()).expect("hay <namedb>, --from o --latest");
+            // `rdo restore <namedb>` restaura en la base de datos del mismo nombre y --from, en la
+            // de target_template; --to elige otra en los dos casos
+            let target = match (to, from) {
+                (Some(to), _) => to,
+                (None, Some(from)) => profile.default_target(&from)?,
+                (None, None) => backup.clone(),
+            };
+            if target != backup {
+                println!("Restaurando el backup '{}' en la base de datos '{}'", backup, target);
             }
+            profile.execute_psql(&backup, &target, &restore)
         }
-        
-        if let Some(namedb) = &args.namedb {
-            profile.execute_psql(namedb)?;
-        } else {
-            eprintln!("Para ejecutar necesita especificar --namedb");
+        Commands::Backup { namedb, conn, backup } => {
+            let profile = connection_profile(&store, requested, &conn)?;
+            profile.create_backup(&namedb, &backup)
         }
-        
-        return Ok(());
-    }
-    
-    // De lo contrario, procede con el flujo normal
-    // Solo ejecutar si tenemos todos los campos necesarios
-    if let (Some(xhost), Some(port), Some(username), Some(container_id)) = 
-        (&args.xhost, args.port, &args.username, &args.container_id) {
-        
-        // crear un instance of Profile
-        let profile = Args::new(
-            xhost.clone(), 
-            port, 
-            username.clone(),
-            args.password.clone(),
-            container_id.clone(),
-            args.dir_backup.clone()
-        );
-        
-        // save the profile
-        if let Err(e) = profile.save() {
-            eprintln!("Error al guardar el perfil: {}", e);
+        Commands::Db { action } => run_db_action(&store, requested, action),
+        Commands::Config { action: ConfigAction::Explain { conn } } => {
+            Config::resolve(&store, requested, &conn.to_profile())?.explain();
+            Ok(())
+        }
+        Commands::Discover { runtime, save, name } => {
+            let base = Config::resolve(&store, requested, &Profile { runtime, ..Profile::default() })?.profile;
+            discover::discover(&mut store, &base, save, name)
         }
-        
-        // print the profile as JSON to console
-        profile.print_json();
-    } else if !args.view_profile && !args.view_backups {
-        println!("Para guardar un perfil, especifique xhost, port, username y container_id");
-        println!("Ejemplo: cargo run -- --xhost db --port 5432 --username odoo --container_id mi-contenedor --dir_backup /tmp/backups");
-        println!("Para autenticación con contraseña: --password mypassword o configure la variable PGPASSWORD");
-        println!("Para ver el perfil guardado: cargo run -- --vp");
-        println!("Para ver las carpetas de backup: cargo run -- --vb");
-        println!("Para restaurar una base de datos:");
-        println!("cargo run -- --run --namedb mi_base_datos");
     }
-    
-    Ok(())
 }
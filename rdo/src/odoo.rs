@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::cli::RestoreArgs;
+use crate::dump::{Dump, DumpFormat, Location};
+use crate::error::RdoError;
+use crate::output::{collect, join};
+use crate::profile::Profile;
+use crate::progress;
+
+/// Entradas del zip que genera /web/database/backup
+const ZIP_DUMP: &str = "dump.sql";
+const ZIP_MANIFEST: &str = "manifest.json";
+const ZIP_FILESTORE: &str = "filestore/";
+
+/// manifest.json de un backup de Odoo
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Manifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odoo_dump: Option<String>,
+    pub db_name: Option<String>,
+    pub version: Option<String>,
+    pub major_version: Option<String>,
+    pub pg_version: Option<String>,
+    /// Módulos instalados y su versión
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, RdoError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    // Resumen del backup antes de restaurarlo
+    pub fn print(&self) {
+        let unknown = "desconocida".to_string();
+        println!(
+            "Backup de Odoo {} (base de datos original: {}, PostgreSQL {})",
+            self.version.as_ref().or(self.major_version.as_ref()).unwrap_or(&unknown),
+            self.db_name.as_deref().unwrap_or("desconocida"),
+            self.pg_version.as_ref().unwrap_or(&unknown),
+        );
+
+        let modules: Vec<&str> = self.modules.keys().map(|m| m.as_str()).collect();
+        println!("Módulos instalados ({}): {}", modules.len(), modules.join(", "));
+    }
+}
+
+impl Profile {
+    // Restaurar un backup ZIP de Odoo: dump.sql en la base de datos y filestore en data_dir
+    pub fn restore_odoo_zip(&self, container_id: &str, dump: &Dump, namedb: &str, opts: &RestoreArgs) -> Result<Output, RdoError> {
+        // ZipArchive necesita acceso aleatorio: un zip del contenedor se copia
+        // comprimido a un archivo temporal del host
+        let (file, _tmp) = match dump.location {
+            Location::Host => {
+                let file = File::open(&dump.path)
+                    .map_err(|e| format!("No se pudo abrir {}: {}", dump.path, e))?;
+                (file, None)
+            }
+            Location::Container => {
+                let tmp = tempfile::NamedTempFile::new()?;
+                let status = self.exec(container_id, &["cat", &dump.path], false)
+                    .stdout(Stdio::from(tmp.reopen()?))
+                    .status()?;
+                if !status.success() {
+                    return Err(format!("No se pudo leer {} del contenedor", dump.path).into());
+                }
+                (tmp.reopen()?, Some(tmp))
+            }
+        };
+        let mut archive = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| format!("{} no es un zip válido: {}", dump.path, e))?;
+
+        match archive.by_name(ZIP_MANIFEST) {
+            Ok(entry) => Manifest::from_reader(entry)?.print(),
+            Err(_) => println!("El backup no incluye {}", ZIP_MANIFEST),
+        }
+
+        let output = {
+            let entry = archive.by_name(ZIP_DUMP)
+                .map_err(|_| format!("El zip {} no contiene {}", dump.path, ZIP_DUMP))?;
+            let bar = progress::bytes_bar(Some(entry.size()));
+            self.pipe_into_restore(container_id, bar.wrap_read(entry), &bar, &dump.path, namedb, opts)?
+        };
+
+        // El filestore solo se copia si la base de datos se restauró bien
+        if output.status.success() {
+            self.restore_filestore(&mut archive, namedb, opts.replace_filestore)?;
+        }
+
+        Ok(output)
+    }
+
+    // Copiar filestore/ del zip a <data_dir>/filestore/<namedb>; con `replace`, el filestore
+    // que ya hubiera se borra antes
+    fn restore_filestore<R: Read + Seek>(&self, archive: &mut ZipArchive<R>, namedb: &str, replace: bool) -> Result<(), RdoError> {
+        // Archivos del filestore con su ruta relativa, ya saneada contra rutas con '..'
+        let mut files = Vec::new();
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let relative = entry.enclosed_name()
+                .and_then(|p| p.strip_prefix(ZIP_FILESTORE).ok().map(Path::to_path_buf));
+            if let Some(relative) = relative {
+                files.push((i, relative));
+            }
+        }
+
+        if files.is_empty() {
+            println!("El backup no incluye filestore");
+            return Ok(());
+        }
+
+        let Some(data_dir) = &self.data_dir else {
+            eprintln!("Aviso: el backup incluye filestore pero no se indicó --data-dir; no se copia");
+            return Ok(());
+        };
+        let target = Path::new("filestore").join(namedb);
+
+        // Sin --replace, los archivos del backup se mezclan con los del filestore anterior
+        let existing = Path::new(data_dir).join(&target);
+        if self.filestore_exists(&existing)? {
+            if replace {
+                println!("Borrando el filestore anterior {}", existing.display());
+                self.remove_filestore(&existing)?;
+            } else {
+                eprintln!(
+                    "Aviso: ya existe {}; los archivos del backup se añaden a los que había (use --replace o --rename-existing para empezar con uno vacío)",
+                    existing.display()
+                );
+            }
+        }
+
+        match &self.odoo_container {
+            Some(odoo_container) => {
+                println!("Copiando filestore ({} archivos) a {}/{} en el contenedor {}", files.len(), data_dir, target.display(), odoo_container);
+
+                // Se envía como tar a `tar -x` dentro del contenedor, que crea los
+                // archivos con el usuario del contenedor (el de Odoo)
+                let mut child = self.exec(odoo_container, &["tar", "-x", "-C", data_dir], true)
+                    .stdin(Stdio::piped())
+                    .spawn()?;
+                let stdin = child.stdin.take().ok_or("No se pudo abrir la entrada de tar")?;
+
+                let mut builder = tar::Builder::new(stdin);
+                for (index, relative) in &files {
+                    let entry = archive.by_index(*index)?;
+                    let mut header = tar::Header::new_gnu();
+                    header.set_size(entry.size());
+                    header.set_mode(0o644);
+                    header.set_mtime(0);
+                    header.set_entry_type(tar::EntryType::Regular);
+                    builder.append_data(&mut header, target.join(relative), entry)?;
+                }
+                drop(builder.into_inner()?);
+
+                if !child.wait()?.success() {
+                    return Err("Error al copiar el filestore al contenedor de Odoo".into());
+                }
+            }
+            None => {
+                let target = PathBuf::from(data_dir).join(&target);
+                println!("Copiando filestore ({} archivos) a {}", files.len(), target.display());
+
+                for (index, relative) in &files {
+                    let mut entry = archive.by_index(*index)?;
+                    let path = target.join(relative);
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    io::copy(&mut entry, &mut File::create(&path)?)?;
+                }
+            }
+        }
+
+        println!("Filestore copiado");
+        Ok(())
+    }
+
+    // Renombrar el filestore de una base de datos renombrada con --rename-existing
+    pub fn rename_filestore(&self, from: &str, to: &str) -> Result<(), RdoError> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(());
+        };
+        let source = Path::new(data_dir).join("filestore").join(from);
+        let target = Path::new(data_dir).join("filestore").join(to);
+        if !self.filestore_exists(&source)? {
+            return Ok(());
+        }
+
+        match &self.odoo_container {
+            Some(odoo_container) => self.odoo_command(
+                odoo_container,
+                &["mv", "--", &source.to_string_lossy(), &target.to_string_lossy()],
+                &format!("renombrar el filestore {}", source.display()),
+            )?,
+            None => fs::rename(&source, &target)?,
+        }
+        println!("Filestore {} renombrado a {}", source.display(), target.display());
+        Ok(())
+    }
+
+    // Si existe el directorio del filestore, en el contenedor de Odoo o en el host
+    fn filestore_exists(&self, dir: &Path) -> Result<bool, RdoError> {
+        match &self.odoo_container {
+            Some(odoo_container) => Ok(self.exec(odoo_container, &["test", "-d", &dir.to_string_lossy()], false).status()?.success()),
+            None => Ok(dir.is_dir()),
+        }
+    }
+
+    fn remove_filestore(&self, dir: &Path) -> Result<(), RdoError> {
+        match &self.odoo_container {
+            Some(odoo_container) => self.odoo_command(
+                odoo_container,
+                &["rm", "-rf", "--", &dir.to_string_lossy()],
+                &format!("borrar el filestore {}", dir.display()),
+            ),
+            None => Ok(fs::remove_dir_all(dir)?),
+        }
+    }
+
+    // Orden en el contenedor de Odoo; `action` describe lo que hace, para el mensaje de error
+    fn odoo_command(&self, odoo_container: &str, argv: &[&str], action: &str) -> Result<(), RdoError> {
+        let output = self.exec(odoo_container, argv, false).output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!("No se pudo {} en el contenedor {}: {}", action, odoo_container, String::from_utf8_lossy(&output.stderr).trim()).into())
+        }
+    }
+
+    // Crear un zip como el de /web/database/backup y guardarlo en `path` dentro del contenedor
+    pub fn create_odoo_zip(&self, container_id: &str, namedb: &str, path: &str) -> Result<(), RdoError> {
+        let manifest = self.odoo_manifest(container_id, namedb)?;
+        manifest.print();
+
+        // ZipWriter necesita acceso aleatorio: el zip se arma en un temporal del host
+        let tmp = tempfile::NamedTempFile::new()?;
+        let mut zip = ZipWriter::new(tmp.reopen()?);
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(true);
+
+        zip.start_file(ZIP_MANIFEST, options)?;
+        serde_json::to_writer_pretty(&mut zip, &manifest)?;
+
+        // Odoo guarda dump.sql en texto plano y sin propietarios
+        let args = self.dump_args(DumpFormat::Plain, None, namedb, None, true)?;
+        println!("Ejecutando en {}:", self.describe(container_id));
+        println!("{}", args.join(" "));
+
+        let mut dump = self.pg_command(container_id, &args, false)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stderr = collect(dump.stderr.take());
+        let mut input = dump.stdout.take().ok_or("No se pudo leer la salida de pg_dump")?;
+
+        zip.start_file(ZIP_DUMP, options)?;
+        let copied = io::copy(&mut input, &mut zip);
+        drop(input);
+        if !dump.wait()?.success() {
+            return Err(RdoError::classify(&String::from_utf8_lossy(&join(stderr))));
+        }
+        copied?;
+
+        self.add_filestore(&mut zip, namedb, options)?;
+        zip.finish()?;
+
+        let status = self.container_writer(container_id, path)
+            .stdin(Stdio::from(tmp.reopen()?))
+            .status()?;
+        if !status.success() {
+            return Err(format!("No se pudo guardar {} en el contenedor", path).into());
+        }
+        Ok(())
+    }
+
+    // Datos del manifest.json: versión de Odoo, de PostgreSQL y módulos instalados
+    fn odoo_manifest(&self, container_id: &str, namedb: &str) -> Result<Manifest, RdoError> {
+        let rows = self.psql_query(container_id, namedb, "SELECT name, latest_version FROM ir_module_module WHERE state = 'installed' ORDER BY name")
+            .map_err(|e| match e {
+                RdoError::Sql { .. } => RdoError::Config(format!("'{}' no parece una base de datos de Odoo: {}", namedb, e)),
+                e => e,
+            })?;
+        let modules: BTreeMap<String, String> = rows.lines()
+            .filter_map(|row| row.split_once('|'))
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect();
+
+        let pg_version = self.psql_query(container_id, namedb, "SHOW server_version")?
+            .split_whitespace().next().map(|v| v.to_string());
+
+        // La versión del módulo base empieza por la de Odoo (16.0.1.3 -> 16.0)
+        let major_version = modules.get("base")
+            .map(|v| v.split('.').take(2).collect::<Vec<_>>().join("."));
+
+        Ok(Manifest {
+            odoo_dump: Some("1".to_string()),
+            db_name: Some(namedb.to_string()),
+            version: major_version.clone(),
+            major_version,
+            pg_version,
+            modules,
+        })
+    }
+
+    // Añadir <data_dir>/filestore/<namedb> al zip como filestore/
+    fn add_filestore<W: Write + Seek>(&self, zip: &mut ZipWriter<W>, namedb: &str, options: SimpleFileOptions) -> Result<(), RdoError> {
+        let Some(data_dir) = &self.data_dir else {
+            eprintln!("Aviso: no se indicó --data-dir; el zip no incluye el filestore");
+            return Ok(());
+        };
+        let source = Path::new(data_dir).join("filestore").join(namedb);
+        let mut count = 0;
+
+        match &self.odoo_container {
+            Some(odoo_container) => {
+                // El contenedor de Odoo envía el filestore como tar por la salida estándar
+                let mut child = self.exec(odoo_container, &["tar", "-c", "-C", &source.to_string_lossy(), "."], false)
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()?;
+                let stderr = collect(child.stderr.take());
+                let mut archive = tar::Archive::new(child.stdout.take().ok_or("No se pudo leer el filestore")?);
+
+                for entry in archive.entries()? {
+                    let mut entry = entry?;
+                    if entry.header().entry_type() != tar::EntryType::Regular {
+                        continue;
+                    }
+                    let relative = entry.path()?.strip_prefix(".").map(Path::to_path_buf)?;
+                    zip.start_file(format!("{}{}", ZIP_FILESTORE, relative.to_string_lossy()), options)?;
+                    io::copy(&mut entry, zip)?;
+                    count += 1;
+                }
+
+                if !child.wait()?.success() {
+                    eprintln!(
+                        "Aviso: no se pudo leer el filestore {} del contenedor {}: {}",
+                        source.display(), odoo_container, String::from_utf8_lossy(&join(stderr)).trim()
+                    );
+                }
+            }
+            None => {
+                if !source.is_dir() {
+                    eprintln!("Aviso: no existe {}; el zip no incluye el filestore", source.display());
+                    return Ok(());
+                }
+                for path in files_under(&source)? {
+                    let relative = path.strip_prefix(&source)?;
+                    zip.start_file(format!("{}{}", ZIP_FILESTORE, relative.to_string_lossy()), options)?;
+                    io::copy(&mut File::open(&path)?, zip)?;
+                    count += 1;
+                }
+            }
+        }
+
+        println!("Filestore incluido ({} archivos)", count);
+        Ok(())
+    }
+}
+
+// Archivos de un directorio y sus subdirectorios
+fn files_under(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(files_under(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::FakeRuntime;
+    use std::io::Cursor;
+
+    // Zip como el de Odoo, con una entrada que intenta salir del filestore
+    fn odoo_zip(files: &[(&str, &str)]) -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        ZipArchive::new(zip.finish().unwrap()).unwrap()
+    }
+
+    fn backup() -> ZipArchive<Cursor<Vec<u8>>> {
+        odoo_zip(&[
+            (ZIP_MANIFEST, "{}"),
+            (ZIP_DUMP, "SELECT 1;"),
+            ("filestore/ab/abcdef", "adjunto"),
+            ("filestore/cd/cdef01", "otro"),
+            ("filestore/../../escapado", "fuera"),
+        ])
+    }
+
+    fn local(data_dir: &Path) -> Profile {
+        Profile { data_dir: Some(data_dir.to_string_lossy().into_owned()), ..Profile::default() }
+    }
+
+    #[test]
+    fn manifest_is_read_with_missing_fields_left_empty() {
+        let json = r#"{"odoo_dump": "1", "db_name": "prod", "version": "17.0", "major_version": "17.0",
+            "pg_version": "15.4", "modules": {"base": "17.0.1.3", "sale": "17.0.1.2"}, "otro": true}"#;
+        let manifest = Manifest::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(manifest.db_name.as_deref(), Some("prod"));
+        assert_eq!(manifest.pg_version.as_deref(), Some("15.4"));
+        assert_eq!(manifest.modules.keys().collect::<Vec<_>>(), ["base", "sale"]);
+
+        let partial = Manifest::from_reader(&br#"{"version": "16.0"}"#[..]).unwrap();
+        assert_eq!(partial.version.as_deref(), Some("16.0"));
+        assert!(partial.db_name.is_none() && partial.modules.is_empty());
+        assert!(Manifest::from_reader(&b"no es json"[..]).is_err());
+    }
+
+    #[test]
+    fn filestore_is_copied_under_data_dir_and_cannot_escape_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("odoo");
+        local(&data_dir).restore_filestore(&mut backup(), "prod", false).unwrap();
+
+        let filestore = data_dir.join("filestore").join("prod");
+        assert_eq!(fs::read_to_string(filestore.join("ab/abcdef")).unwrap(), "adjunto");
+        assert_eq!(fs::read_to_string(filestore.join("cd/cdef01")).unwrap(), "otro");
+        // enclosed_name descarta la entrada con '..'
+        assert_eq!(files_under(dir.path()).unwrap().len(), 2);
+        assert!(!dir.path().join("escapado").exists());
+    }
+
+    #[test]
+    fn filestore_is_not_copied_without_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        Profile::default().restore_filestore(&mut backup(), "prod", false).unwrap();
+        local(dir.path()).restore_filestore(&mut odoo_zip(&[(ZIP_DUMP, "SELECT 1;")]), "prod", false).unwrap();
+        assert!(!dir.path().join("filestore").exists());
+    }
+
+    #[test]
+    fn replace_starts_from_an_empty_filestore_and_otherwise_files_are_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("filestore/prod/zz/viejo");
+        let profile = local(dir.path());
+
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, "anterior").unwrap();
+        profile.restore_filestore(&mut backup(), "prod", false).unwrap();
+        assert!(old.exists());
+
+        profile.restore_filestore(&mut backup(), "prod", true).unwrap();
+        assert!(!old.exists());
+        assert_eq!(files_under(&dir.path().join("filestore/prod")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn renamed_databases_keep_their_filestore() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = local(dir.path());
+        fs::create_dir_all(dir.path().join("filestore/prod/ab")).unwrap();
+        fs::write(dir.path().join("filestore/prod/ab/abcdef"), "adjunto").unwrap();
+
+        profile.rename_filestore("prod", "prod_20250428_153000").unwrap();
+        assert!(!dir.path().join("filestore/prod").exists());
+        assert!(dir.path().join("filestore/prod_20250428_153000/ab/abcdef").exists());
+        // Sin filestore no hay nada que renombrar
+        profile.rename_filestore("nueva", "nueva_20250428_153000").unwrap();
+        Profile::default().rename_filestore("prod", "otra").unwrap();
+    }
+
+    #[test]
+    fn filestore_in_the_odoo_container_is_renamed_there() {
+        let fake = FakeRuntime::new(|_| (0, String::new(), String::new()));
+        let profile = Profile {
+            data_dir: Some("/var/lib/odoo".into()),
+            odoo_container: Some("odoo".into()),
+            executor: Some(fake.clone()),
+            ..Profile::default()
+        };
+        profile.rename_filestore("prod", "prod_20250428_153000").unwrap();
+        assert_eq!(fake.calls(), [
+            vec!["odoo", "test", "-d", "/var/lib/odoo/filestore/prod"],
+            vec!["odoo", "mv", "--", "/var/lib/odoo/filestore/prod", "/var/lib/odoo/filestore/prod_20250428_153000"],
+        ]);
+
+        profile.remove_filestore(Path::new("/var/lib/odoo/filestore/prod")).unwrap();
+        assert_eq!(fake.calls().last().unwrap(), &["odoo", "rm", "-rf", "--", "/var/lib/odoo/filestore/prod"]);
+    }
+}
@@ -0,0 +1,348 @@
+use indicatif::ProgressBar;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+
+use crate::error::is_error_line;
+
+/// Líneas de cada salida que se conservan en memoria para informar de errores;
+/// el resto solo queda en el registro de la ejecución
+const KEEP_LINES: usize = 200;
+
+/// Mensajes de `pg_restore --verbose` que anuncian un elemento del TOC
+const ENTRY_PREFIXES: &[&str] = &[
+    "pg_restore: creating ",
+    "pg_restore: processing data for table ",
+    "pg_restore: executing ",
+];
+
+/// Nivel de detalle con que se muestra en la terminal la salida de psql, pg_restore y pg_dump
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub enum Verbosity {
+    /// Sin barra de progreso ni mensajes de las herramientas; solo errores
+    Quiet,
+    /// Barra de progreso y, si algo falla, el primer error y los últimos mensajes
+    #[default]
+    Normal,
+    /// Además, los avisos y errores (stderr) en vivo
+    Verbose,
+    /// Toda la salida en vivo
+    Debug,
+}
+
+impl Verbosity {
+    // Nivel a partir de -q y del número de -v
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            _ => Verbosity::Debug,
+        }
+    }
+}
+
+/// Salida de un proceso que se está leyendo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Registro de la ejecución: toda la salida de las herramientas, en un archivo por ejecución
+struct RunLog {
+    verbosity: Verbosity,
+    path: PathBuf,
+    /// Se crea al escribir la primera línea; `None` dentro si no se pudo crear
+    file: Mutex<Option<Option<File>>>,
+}
+
+static RUN_LOG: OnceLock<RunLog> = OnceLock::new();
+
+// Configurar el nivel de detalle y el archivo de registro de esta ejecución
+pub fn init(verbosity: Verbosity, log_file: Option<PathBuf>, command: &str) {
+    let path = log_file.unwrap_or_else(|| default_log_path(command));
+    let _ = RUN_LOG.set(RunLog::new(verbosity, path));
+}
+
+// <log_dir>/<fecha>-<comando>.log
+fn default_log_path(command: &str) -> PathBuf {
+    log_dir().join(format!("{}-{}.log", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"), command))
+}
+
+// Directorio de los registros: ~/.local/state/rdo/logs (o la caché en sistemas sin state_dir)
+fn log_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rdo")
+        .join("logs")
+}
+
+pub fn verbosity() -> Verbosity {
+    RUN_LOG.get().map(|log| log.verbosity).unwrap_or_default()
+}
+
+// Ruta del registro, si ya se escribió algo en él
+pub fn log_path() -> Option<&'static Path> {
+    RUN_LOG.get()?.written_path()
+}
+
+// Añadir una línea al registro de la ejecución, si lo hay
+fn log_line(line: &str) {
+    if let Some(log) = RUN_LOG.get() {
+        log.write_line(line);
+    }
+}
+
+impl RunLog {
+    fn new(verbosity: Verbosity, path: PathBuf) -> Self {
+        RunLog { verbosity, path, file: Mutex::new(None) }
+    }
+
+    fn written_path(&self) -> Option<&Path> {
+        let file = self.file.lock().ok()?;
+        matches!(*file, Some(Some(_))).then_some(self.path.as_path())
+    }
+
+    // Añadir una línea, creando el archivo la primera vez
+    fn write_line(&self, line: &str) {
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+
+        let file = file.get_or_insert_with(|| match open_log(&self.path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Aviso: no se pudo crear el registro {}: {}", self.path.display(), e);
+                None
+            }
+        });
+        if let Some(file) = file {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    File::options().create(true).append(true).open(path)
+}
+
+// Anotar en el registro el comando que se va a ejecutar, sin la contraseña
+pub fn log_command(cmd: &Command) {
+    log_line(&command_line(cmd));
+}
+
+fn command_line(cmd: &Command) -> String {
+    let mut line = format!("$ {}", cmd.get_program().to_string_lossy());
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        match arg.split_once('=') {
+            Some(("PGPASSWORD", _)) => line.push_str(" PGPASSWORD=********"),
+            _ => {
+                line.push(' ');
+                line.push_str(&arg);
+            }
+        }
+    }
+    line
+}
+
+// Leer una salida de psql, pg_restore o pg_dump línea a línea en un hilo aparte.
+// Cada línea va al registro; en memoria solo quedan las últimas KEEP_LINES y, delante,
+// la primera línea de error, que es la que clasifica el fallo aunque le sigan miles.
+// Los mensajes de progreso de `pg_restore --verbose` avanzan la barra y, con `tail`,
+// la última línea se muestra junto a ella.
+pub fn watch<R: Read + Send + 'static>(pipe: Option<R>, stream: Stream, bar: ProgressBar, tail: bool) -> JoinHandle<Vec<u8>> {
+    let verbosity = verbosity();
+    thread::spawn(move || {
+        let mut kept: VecDeque<String> = VecDeque::new();
+        // Primera línea de error y cuántas líneas se habían guardado antes de ella
+        let mut first_error: Option<(usize, String)> = None;
+        let mut pushed = 0;
+        let Some(pipe) = pipe else {
+            return Vec::new();
+        };
+
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while let Ok(n) = reader.read_until(b'\n', &mut line) {
+            if n == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end();
+            log_line(text);
+
+            if ENTRY_PREFIXES.iter().any(|p| text.starts_with(p)) {
+                // En paralelo, pg_restore puede anunciar un elemento más de una vez
+                if bar.length().is_none_or(|len| bar.position() < len) {
+                    bar.inc(1);
+                }
+            } else {
+                let echo = match stream {
+                    Stream::Stdout => verbosity >= Verbosity::Debug,
+                    Stream::Stderr => verbosity >= Verbosity::Verbose,
+                };
+                if echo {
+                    bar.suspend(|| eprintln!("{}", text));
+                }
+                if first_error.is_none() && is_error_line(text) {
+                    first_error = Some((pushed, text.to_string()));
+                }
+                if kept.len() == KEEP_LINES {
+                    kept.pop_front();
+                }
+                kept.push_back(text.to_string());
+                pushed += 1;
+            }
+
+            if tail && !text.is_empty() {
+                bar.set_message(text.to_string());
+            }
+            line.clear();
+        }
+
+        // Si la primera línea de error ya salió de las últimas, se conserva delante de ellas
+        if let Some((at, text)) = first_error.filter(|(at, _)| *at < pushed - kept.len()) {
+            let skipped = pushed - kept.len() - at - 1;
+            if skipped > 0 {
+                kept.push_front(format!("... ({} líneas más en el registro)", skipped));
+            }
+            kept.push_front(text);
+        }
+
+        let mut buf = Vec::new();
+        for text in kept {
+            buf.extend_from_slice(text.as_bytes());
+            buf.push(b'\n');
+        }
+        buf
+    })
+}
+
+// Ejecutar un comando leyendo sus salidas a medida que llegan, como `Command::output`
+// pero sin acumularlas completas en memoria
+pub fn run(cmd: &mut Command) -> io::Result<Output> {
+    log_command(cmd);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = watch(child.stdout.take(), Stream::Stdout, ProgressBar::hidden(), false);
+    let stderr = watch(child.stderr.take(), Stream::Stderr, ProgressBar::hidden(), false);
+    let status = child.wait()?;
+    Ok(Output { status, stdout: join(stdout), stderr: join(stderr) })
+}
+
+// Leer una tubería completa en un hilo aparte (salidas cortas, como la de un descompresor)
+pub fn collect<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+pub fn join(handle: JoinHandle<Vec<u8>>) -> Vec<u8> {
+    handle.join().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn lines(output: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(output).lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn only_the_last_lines_are_kept_in_memory() {
+        let input: String = (1..=KEEP_LINES + 50).map(|i| format!("línea {}\n", i)).collect();
+        let kept = lines(&join(watch(Some(Cursor::new(input)), Stream::Stderr, ProgressBar::hidden(), false)));
+        assert_eq!(kept.len(), KEEP_LINES);
+        assert_eq!(kept[0], "línea 51");
+        assert_eq!(kept.last().unwrap(), &format!("línea {}", KEEP_LINES + 50));
+
+        assert!(join(watch(None::<Cursor<Vec<u8>>>, Stream::Stdout, ProgressBar::hidden(), false)).is_empty());
+    }
+
+    #[test]
+    fn the_first_error_line_is_kept_ahead_of_the_last_lines() {
+        let mut input = String::from("SET\npsql:dump.sql:3: ERROR:  42P01: relation \"falta\" does not exist\n");
+        input.extend((1..=KEEP_LINES + 50).map(|i| format!("psql:dump.sql:{}: ERROR:  25P02: current transaction is aborted\n", i + 3)));
+        let output = join(watch(Some(Cursor::new(input)), Stream::Stderr, ProgressBar::hidden(), false));
+        let kept = lines(&output);
+        assert_eq!(kept.len(), KEEP_LINES + 2);
+        assert_eq!(kept[0], "psql:dump.sql:3: ERROR:  42P01: relation \"falta\" does not exist");
+        assert_eq!(kept[1], "... (50 líneas más en el registro)");
+        assert_eq!(kept.last().unwrap(), &format!("psql:dump.sql:{}: ERROR:  25P02: current transaction is aborted", KEEP_LINES + 53));
+        assert!(matches!(
+            crate::error::RdoError::classify(&String::from_utf8_lossy(&output)),
+            crate::error::RdoError::Sql { sqlstate: Some(code), .. } if code == "42P01"
+        ));
+
+        // Si sigue entre las últimas líneas, no se repite
+        let input = "SET\nERROR:  42P01: relation \"falta\" does not exist\nSET\n";
+        let kept = lines(&join(watch(Some(Cursor::new(input)), Stream::Stderr, ProgressBar::hidden(), false)));
+        assert_eq!(kept, ["SET", "ERROR:  42P01: relation \"falta\" does not exist", "SET"]);
+    }
+
+    #[test]
+    fn toc_entries_advance_the_bar_instead_of_being_kept() {
+        let input = "pg_restore: creating TABLE public.res_partner\npg_restore: processing data for table \"public.res_partner\"\nERROR: algo falló\n";
+        let bar = ProgressBar::hidden();
+        bar.set_length(10);
+        let kept = lines(&join(watch(Some(Cursor::new(input)), Stream::Stderr, bar.clone(), true)));
+        assert_eq!(kept, ["ERROR: algo falló"]);
+        assert_eq!(bar.position(), 2);
+        assert_eq!(bar.message(), "ERROR: algo falló");
+    }
+
+    #[test]
+    fn logged_commands_never_show_the_password() {
+        let mut cmd = Command::new("docker");
+        cmd.args(["exec", "-e", "PGPASSWORD=s3cr3t", "db", "psql", "-c", "SELECT 'PGPASSWORD'"]);
+        let line = command_line(&cmd);
+        assert!(!line.contains("s3cr3t"), "{}", line);
+        assert_eq!(line, "$ docker exec -e PGPASSWORD=******** db psql -c SELECT 'PGPASSWORD'");
+
+        // Con docker la contraseña solo viaja en el entorno, que no se anota
+        let cmd = crate::runtime::Runtime::Docker.executor().command("db", &["psql"], &[("PGPASSWORD", "s3cr3t")], false);
+        assert_eq!(command_line(&cmd), "$ docker exec -e PGPASSWORD db psql");
+    }
+
+    #[test]
+    fn run_log_is_created_on_the_first_line_under_the_state_directory() {
+        let default = default_log_path("restore");
+        assert!(default.starts_with(log_dir()), "{}", default.display());
+        assert!(default.to_string_lossy().ends_with("-restore.log"));
+        if let Some(state) = dirs::state_dir() {
+            assert_eq!(log_dir(), state.join("rdo").join("logs"));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = RunLog::new(Verbosity::Normal, dir.path().join("state/rdo/logs/run.log"));
+        assert_eq!(log.written_path(), None);
+        assert!(!log.path.exists());
+
+        log.write_line("$ psql --dbname prod");
+        log.write_line("ERROR:  relation \"x\" does not exist");
+        assert_eq!(log.written_path(), Some(log.path.as_path()));
+        assert_eq!(lines(&fs::read(&log.path).unwrap()), ["$ psql --dbname prod", "ERROR:  relation \"x\" does not exist"]);
+    }
+}
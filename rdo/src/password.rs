@@ -0,0 +1,170 @@
+// Contraseña de PostgreSQL. Además de --password, RDO_PASSWORD y PGPASSWORD, se obtiene
+// de `password_command` o de ~/.pgpass (o PGPASSFILE). Los tres se leen en el host, porque
+// psql se ejecuta en el contenedor, donde no están ni el archivo ni la orden.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::error::RdoError;
+use crate::output;
+use crate::profile::Profile;
+
+/// Variable de entorno de libpq con la ruta del archivo de contraseñas
+const PGPASSFILE_ENV: &str = "PGPASSFILE";
+
+impl Profile {
+    // Completar la contraseña si no se indicó directamente: primero `password_command`
+    // y, si no hay, la primera línea de .pgpass que coincida con el host, el puerto y el usuario
+    pub fn resolve_password(&mut self) -> Result<(), RdoError> {
+        if self.password.is_some() {
+            return Ok(());
+        }
+        if let Some(command) = &self.password_command {
+            self.password = Some(run_password_command(command)?);
+            return Ok(());
+        }
+        if let (Some(xhost), Some(port), Some(username)) = (&self.xhost, self.port, &self.username) {
+            self.password = pgpass_password(xhost, port, username)?;
+        }
+        Ok(())
+    }
+}
+
+// Ejecutar `password_command` con sh; la contraseña es la primera línea de su salida.
+// stdin y stderr quedan en la terminal, por si la orden pide una frase de paso (gpg, pass)
+fn run_password_command(command: &str) -> Result<String, RdoError> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]).stdin(Stdio::inherit()).stderr(Stdio::inherit());
+    output::log_command(&cmd);
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(RdoError::Config(format!("password_command '{}' terminó con {}", command, output.status)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next() {
+        Some(password) if !password.is_empty() => Ok(password.to_string()),
+        _ => Err(RdoError::Config(format!("password_command '{}' no imprimió ninguna contraseña", command))),
+    }
+}
+
+// Ruta del archivo de contraseñas: PGPASSFILE o ~/.pgpass, como libpq
+fn pgpass_path() -> Option<PathBuf> {
+    match env::var_os(PGPASSFILE_ENV) {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::home_dir().map(|home| home.join(".pgpass")),
+    }
+}
+
+fn pgpass_password(xhost: &str, port: u16, username: &str) -> Result<Option<String>, RdoError> {
+    let Some(path) = pgpass_path() else {
+        return Ok(None);
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // libpq no usa el archivo si el grupo u otros pueden leerlo; hacemos lo mismo
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            eprintln!(
+                "Aviso: se ignora {} porque el grupo u otros tienen acceso; use chmod 0600",
+                path.display()
+            );
+            return Ok(None);
+        }
+    }
+
+    Ok(pgpass_lookup(&content, xhost, port, username))
+}
+
+// Buscar en un archivo .pgpass (hostname:port:database:username:password) la primera línea
+// que coincida. rdo se conecta a varias bases (postgres y la de destino), así que el campo
+// de la base de datos no se compara
+fn pgpass_lookup(content: &str, xhost: &str, port: u16, username: &str) -> Option<String> {
+    let port = port.to_string();
+    content.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(split_pgpass_line)
+        .find(|fields| {
+            fields.len() == 5
+                && matches(&fields[0], xhost)
+                && matches(&fields[1], &port)
+                && matches(&fields[3], username)
+        })
+        .map(|mut fields| fields.remove(4))
+}
+
+fn matches(pattern: &str, value: &str) -> bool {
+    pattern == "*" || pattern == value
+}
+
+// Separar los campos por ':', con `\:` y `\\` como escapes
+fn split_pgpass_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().unwrap().push(next);
+                }
+            }
+            // La contraseña es el último campo y puede contener ':' sin escapar
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGPASS: &str = "\
+# comentario
+otro:5432:*:odoo:no-es-esta
+db:5432:*:odoo:pa\\:ss\\\\word
+*:*:*:postgres:comodin
+db:5432:*:odoo:no-gana-la-segunda
+";
+
+    #[test]
+    fn pgpass_takes_the_first_matching_line() {
+        assert_eq!(pgpass_lookup(PGPASS, "db", 5432, "odoo").as_deref(), Some("pa:ss\\word"));
+        assert_eq!(pgpass_lookup(PGPASS, "cualquiera", 6543, "postgres").as_deref(), Some("comodin"));
+        assert_eq!(pgpass_lookup(PGPASS, "db", 5433, "odoo"), None);
+    }
+
+    #[test]
+    fn password_command_fills_the_password_only_when_missing() {
+        let mut p = Profile { password_command: Some("printf 'secreto\\nresto'".into()), ..Profile::default() };
+        p.resolve_password().unwrap();
+        assert_eq!(p.password.as_deref(), Some("secreto"));
+
+        let mut p = Profile {
+            password: Some("directa".into()),
+            password_command: Some("exit 1".into()),
+            ..Profile::default()
+        };
+        p.resolve_password().unwrap();
+        assert_eq!(p.password.as_deref(), Some("directa"));
+    }
+
+    #[test]
+    fn failing_or_silent_password_command_is_a_config_error() {
+        for command in ["exit 3", "true"] {
+            let mut p = Profile { password_command: Some(command.into()), ..Profile::default() };
+            assert!(matches!(p.resolve_password(), Err(RdoError::Config(_))), "{}", command);
+        }
+    }
+}
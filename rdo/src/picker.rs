@@ -0,0 +1,311 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::{stdout, IsTerminal};
+
+use crate::backups::BackupInfo;
+use crate::db::check_db_name;
+use crate::error::RdoError;
+use crate::prompt;
+
+/// Backup elegido y base de datos donde restaurarlo
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choice {
+    pub backup: BackupInfo,
+    pub namedb: String,
+}
+
+/// Resultado de una tecla
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Cancel,
+    /// Índice en `backups` y base de datos destino
+    Done(usize, String),
+}
+
+/// Estado del selector: búsqueda sobre los backups con dump y, tras elegir uno,
+/// el nombre de la base de datos destino
+struct Picker {
+    backups: Vec<BackupInfo>,
+    query: String,
+    /// Índices de `backups` que coinciden con la búsqueda, del más al menos parecido
+    matches: Vec<usize>,
+    list: ListState,
+    /// Nombre de la base de datos destino, mientras se escribe
+    target: Option<String>,
+    /// Destino que se propone para cada backup
+    default_target: Box<dyn Fn(&BackupInfo) -> String>,
+    error: Option<String>,
+}
+
+// Abrir el selector sobre el catálogo; `None` si el usuario lo cierra sin elegir.
+// Al elegir un backup se propone como destino `default_target(backup)`
+pub fn pick_backup(backups: Vec<BackupInfo>, default_target: impl Fn(&BackupInfo) -> String + 'static) -> Result<Option<Choice>, RdoError> {
+    let backups: Vec<BackupInfo> = backups.into_iter().filter(|b| b.dump.is_some()).collect();
+    if backups.is_empty() {
+        return Err(RdoError::MissingDump("No hay ningún backup con dump para restaurar".into()));
+    }
+    if !prompt::can_ask() || !stdout().is_terminal() {
+        return Err(RdoError::InputRequired(
+            "Hace falta elegir el backup, pero no hay una terminal interactiva. Use 'rdo restore <namedb>'".into(),
+        ));
+    }
+
+    let mut picker = Picker::new(backups, Box::new(default_target));
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Picker {
+    fn new(backups: Vec<BackupInfo>, default_target: Box<dyn Fn(&BackupInfo) -> String>) -> Self {
+        let mut picker = Self {
+            backups,
+            query: String::new(),
+            matches: Vec::new(),
+            list: ListState::default(),
+            target: None,
+            default_target,
+            error: None,
+        };
+        picker.search();
+        picker
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<Choice>, RdoError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match self.handle(key) {
+                    Step::Continue => {}
+                    Step::Cancel => return Ok(None),
+                    Step::Done(index, namedb) => return Ok(Some(Choice { backup: self.backups[index].clone(), namedb })),
+                }
+            }
+        }
+    }
+
+    // Recalcular las coincidencias con la búsqueda actual
+    fn search(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self.backups.iter().enumerate()
+            .filter_map(|(i, b)| {
+                let text = format!("{} {}", b.name, b.db_name.as_deref().unwrap_or_default());
+                fuzzy_score(&self.query, &text).map(|score| (score, i))
+            })
+            .collect();
+        // A igual puntuación se mantiene el orden del catálogo
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.list.select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    fn selected(&self) -> Option<&BackupInfo> {
+        self.list.selected().and_then(|i| self.matches.get(i)).map(|i| &self.backups[*i])
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Step {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Step::Cancel;
+        }
+        self.error = None;
+
+        // Segundo paso: nombre de la base de datos destino
+        if let Some(target) = &mut self.target {
+            match key.code {
+                KeyCode::Esc => self.target = None,
+                KeyCode::Backspace => {
+                    target.pop();
+                }
+                KeyCode::Char(c) => target.push(c),
+                KeyCode::Enter => {
+                    let namedb = target.trim().to_string();
+                    if let Err(e) = check_db_name(&namedb) {
+                        self.error = Some(e.to_string());
+                        return Step::Continue;
+                    }
+                    let index = self.list.selected().and_then(|i| self.matches.get(i)).copied().expect("hay un backup elegido");
+                    return Step::Done(index, namedb);
+                }
+                _ => {}
+            }
+            return Step::Continue;
+        }
+
+        match key.code {
+            KeyCode::Esc => return Step::Cancel,
+            KeyCode::Up => self.list.select_previous(),
+            KeyCode::Down => self.list.select_next(),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.search();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.search();
+            }
+            // Se propone el destino de --to o de target_template, que se puede cambiar
+            KeyCode::Enter => match self.selected() {
+                Some(backup) => self.target = Some((self.default_target)(backup)),
+                None => self.error = Some("Ningún backup coincide con la búsqueda".into()),
+            },
+            _ => {}
+        }
+        Step::Continue
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, body, help] = Layout::vertical([Constraint::Length(3), Constraint::Min(5), Constraint::Length(1)])
+            .areas(frame.area());
+        let [list, preview] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(body);
+
+        let (title, text) = match &self.target {
+            Some(target) => (" Base de datos destino ", target.as_str()),
+            None => (" Buscar backup ", self.query.as_str()),
+        };
+        frame.render_widget(Paragraph::new(format!("{}█", text)).block(Block::bordered().title(title)), input);
+
+        let items: Vec<ListItem> = self.matches.iter()
+            .map(|i| &self.backups[*i])
+            .map(|b| ListItem::new(Line::from(vec![
+                Span::raw(b.name.clone()),
+                // Con plantilla de ruta, la fecha distingue los dumps de un mismo nombre
+                Span::raw(b.date.as_ref().map(|d| format!("  {}", d)).unwrap_or_default()),
+                Span::raw(format!("  {}  {}", b.size_label(), b.date_label())).dim(),
+            ])))
+            .collect();
+        let title = format!(" Backups ({}/{}) ", self.matches.len(), self.backups.len());
+        let items = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(items, list, &mut self.list);
+
+        let details = self.selected().map(preview_lines).unwrap_or_default();
+        frame.render_widget(Paragraph::new(details).block(Block::bordered().title(" Detalles ")), preview);
+
+        let hint = match (&self.error, &self.target) {
+            (Some(error), _) => Line::from(error.clone()).red(),
+            (None, Some(_)) => Line::from("Enter: restaurar  Esc: volver  Ctrl-C: salir").dim(),
+            (None, None) => Line::from("Escriba para buscar  ↑/↓: elegir  Enter: continuar  Esc: salir").dim(),
+        };
+        frame.render_widget(hint, help);
+    }
+}
+
+// Datos del backup para el panel de detalles
+fn preview_lines(b: &BackupInfo) -> Vec<Line<'static>> {
+    let dash = || "-".to_string();
+    let rows = [
+        ("Backup", b.name.clone()),
+        ("Carpeta", b.path.clone()),
+        ("Dump", b.dump.clone().unwrap_or_else(dash)),
+        ("Fecha", b.date.clone().unwrap_or_else(dash)),
+        ("Formato", b.format.map(|f| f.to_string()).unwrap_or_else(dash)),
+        ("Compresión", b.compression_label()),
+        ("Tamaño", b.size_label()),
+        ("Modificado", b.date_label()),
+        ("Odoo", b.odoo_version.clone().unwrap_or_else(dash)),
+        ("Base original", b.db_name.clone().unwrap_or_else(dash)),
+    ];
+    rows.into_iter()
+        .map(|(label, value)| Line::from(vec![Span::raw(format!("{:14}", label)).bold(), Span::raw(value)]))
+        .collect()
+}
+
+// Puntuación de `text` para una búsqueda difusa: las letras de `query` deben aparecer en orden;
+// suman más las seguidas y las que empiezan una palabra, y restan los saltos.
+// `None` si no coincide
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|c| *c == q)?;
+        score += 10;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 15;
+        }
+        if found == 0 || matches!(text[found - 1], '_' | '-' | '.' | ' ' | '/') {
+            score += 10;
+        }
+        score -= (found - pos) as i64;
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(name: &str) -> BackupInfo {
+        BackupInfo { name: name.into(), dump: Some("dump.sql".into()), ..BackupInfo::default() }
+    }
+
+    fn press(picker: &mut Picker, keys: &str) -> Step {
+        let mut step = Step::Continue;
+        for c in keys.chars() {
+            step = picker.handle(KeyEvent::from(KeyCode::Char(c)));
+        }
+        step
+    }
+
+    #[test]
+    fn fuzzy_search_prefers_consecutive_and_word_starts() {
+        assert!(fuzzy_score("prd", "staging").is_none());
+        assert!(fuzzy_score("", "cualquiera").is_some());
+        assert!(fuzzy_score("prod", "prod_2025") > fuzzy_score("prod", "p_r_o_d"));
+        assert!(fuzzy_score("p25", "prod_2025") > fuzzy_score("p25", "xprodx25"));
+        assert_eq!(fuzzy_score("PROD", "prod"), fuzzy_score("prod", "prod"));
+    }
+
+    #[test]
+    fn search_then_target_name_then_enter_chooses() {
+        let default_target = Box::new(|b: &BackupInfo| b.name.clone());
+        let mut picker = Picker::new(vec![backup("staging"), backup("prod_2025_04"), backup("prod_2024_12")], default_target);
+        press(&mut picker, "pr25");
+        assert_eq!(picker.matches, [1]);
+
+        picker.handle(KeyEvent::from(KeyCode::Backspace));
+        picker.handle(KeyEvent::from(KeyCode::Backspace));
+        assert_eq!(picker.matches, [1, 2]);
+        picker.handle(KeyEvent::from(KeyCode::Down));
+        assert_eq!(picker.selected().unwrap().name, "prod_2024_12");
+        picker.handle(KeyEvent::from(KeyCode::Up));
+
+        picker.handle(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(picker.target.as_deref(), Some("prod_2025_04"));
+        press(&mut picker, "_copia");
+        let step = picker.handle(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(step, Step::Done(1, "prod_2025_04_copia".into()));
+    }
+
+    #[test]
+    fn invalid_target_names_stay_in_the_picker() {
+        let mut picker = Picker::new(vec![backup("prod")], Box::new(|b| format!("{}_copia", b.name)));
+        picker.handle(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(picker.target.as_deref(), Some("prod_copia"));
+        for _ in 0.."prod_copia".len() {
+            picker.handle(KeyEvent::from(KeyCode::Backspace));
+        }
+        press(&mut picker, "a/b");
+        assert_eq!(picker.handle(KeyEvent::from(KeyCode::Enter)), Step::Continue);
+        assert!(picker.error.is_some());
+
+        // Esc vuelve a la búsqueda y, otra vez, sale
+        picker.handle(KeyEvent::from(KeyCode::Esc));
+        assert!(picker.target.is_none());
+        assert_eq!(picker.handle(KeyEvent::from(KeyCode::Esc)), Step::Cancel);
+    }
+}
@@ -0,0 +1,638 @@
+use serde::{Serialize, Deserialize};
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::RdoError;
+use crate::runtime::{ContainerRuntime, Runtime};
+use crate::secret::{self, Encryption, SecretKey};
+
+/// Archivo de perfil de versiones anteriores (un único perfil en el directorio actual)
+const LEGACY_PROFILE_FILE: &str = "profile.json";
+
+/// Nombre con el que se importa el perfil heredado
+const LEGACY_PROFILE_NAME: &str = "default";
+
+/// Lo que se muestra en lugar de la contraseña
+const MASKED_PASSWORD: &str = "***";
+
+/// Nombre con que se muestra el destino de las órdenes en modo local
+const LOCAL_CONTAINER: &str = "host";
+
+/// Datos de conexión guardados en un perfil
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    /// Host de la base de datos
+    pub xhost: Option<String>,
+
+    /// Puerto de la base de datos
+    pub port: Option<u16>,
+
+    /// Usuario de la base de datos
+    pub username: Option<String>,
+
+    /// Contraseña de la base de datos; en el archivo, cifrada (enc:v1:...)
+    pub password: Option<String>,
+
+    /// Orden que imprime la contraseña (p. ej. `pass show db/staging`); se ejecuta en el host al conectar
+    pub password_command: Option<String>,
+
+    /// Contenedor: ID, nombre, <proyecto>/<servicio> de compose o label:clave=valor
+    pub container_id: Option<String>,
+
+    /// Ruta base para backups (dentro del contenedor)
+    pub dir_backup: Option<String>,
+
+    /// Dónde están los dumps, como `{dir}/{name}/{date}/{name}.{ext}`; sin plantilla, `<dir_backup>/<namedb>/dump.*`
+    pub path_template: Option<String>,
+
+    /// Nombre de la base de datos destino de `rdo restore --from` sin --to; por defecto `{backup}_{date}`
+    pub target_template: Option<String>,
+
+    /// Directorio de datos de Odoo (data_dir), donde se copia el filestore
+    pub data_dir: Option<String>,
+
+    /// Contenedor de Odoo que contiene data_dir; sin él, data_dir es una ruta del host
+    pub odoo_container: Option<String>,
+
+    /// Programa con que se ejecutan las órdenes: docker (por defecto), podman, nerdctl o local
+    pub runtime: Option<Runtime>,
+
+    /// Ejecutor ya construido; si no hay, se usa el de `runtime` (las pruebas ponen aquí uno falso)
+    #[serde(skip)]
+    pub executor: Option<Arc<dyn ContainerRuntime>>,
+}
+
+impl Profile {
+    // Un perfil es utilizable si tiene los datos mínimos de conexión
+    pub fn is_complete(&self) -> bool {
+        self.xhost.is_some() && self.port.is_some() && self.username.is_some() && self.container().is_some()
+    }
+
+    // Contenedor donde se ejecutan las órdenes; en modo local no hace falta ninguno
+    pub fn container(&self) -> Option<&str> {
+        match self.runtime.unwrap_or_default() {
+            Runtime::Local => Some(LOCAL_CONTAINER),
+            _ => self.container_id.as_deref(),
+        }
+    }
+
+    // Dónde se ejecutan las órdenes, para los mensajes: "el contenedor <id>" o "el host"
+    pub fn describe(&self, container: &str) -> String {
+        match self.runtime.unwrap_or_default() {
+            Runtime::Local => "el host".to_string(),
+            _ => format!("el contenedor {}", container),
+        }
+    }
+
+    // Ejecutor de las órdenes del perfil
+    pub fn executor(&self) -> Arc<dyn ContainerRuntime> {
+        self.executor.clone().unwrap_or_else(|| self.runtime.unwrap_or_default().executor())
+    }
+
+    // Sobrescribir con los campos definidos en `other`
+    pub fn merge(&mut self, other: &Profile) {
+        if other.xhost.is_some() {
+            self.xhost = other.xhost.clone();
+        }
+        if other.port.is_some() {
+            self.port = other.port;
+        }
+        if other.username.is_some() {
+            self.username = other.username.clone();
+        }
+        if other.password.is_some() {
+            self.password = other.password.clone();
+        }
+        if other.password_command.is_some() {
+            self.password_command = other.password_command.clone();
+        }
+        if other.container_id.is_some() {
+            self.container_id = other.container_id.clone();
+        }
+        if other.dir_backup.is_some() {
+            self.dir_backup = other.dir_backup.clone();
+        }
+        if other.path_template.is_some() {
+            self.path_template = other.path_template.clone();
+        }
+        if other.target_template.is_some() {
+            self.target_template = other.target_template.clone();
+        }
+        if other.data_dir.is_some() {
+            self.data_dir = other.data_dir.clone();
+        }
+        if other.odoo_container.is_some() {
+            self.odoo_container = other.odoo_container.clone();
+        }
+        if other.runtime.is_some() {
+            self.runtime = other.runtime;
+        }
+    }
+
+    // Function to print the JSON to console
+    pub fn print_json(&self) {
+        println!("{}", self.to_masked_json());
+    }
+
+    // JSON del perfil con la contraseña oculta, cifrada o no: la salida acaba en el
+    // historial de la terminal y en los registros de CI
+    fn to_masked_json(&self) -> String {
+        let mut shown = self.clone();
+        if shown.password.is_some() {
+            shown.password = Some(MASKED_PASSWORD.to_string());
+        }
+        serde_json::to_string_pretty(&shown).unwrap()
+    }
+}
+
+/// Contenido del archivo de perfiles
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct StoreData {
+    /// Perfil usado cuando no se indica `--profile`
+    default: Option<String>,
+
+    /// Perfiles guardados, ordenados por nombre
+    profiles: BTreeMap<String, Profile>,
+
+    /// Sal y parámetros de la clave con que se cifran las contraseñas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
+}
+
+/// Almacén de perfiles con nombre
+#[derive(Debug)]
+pub struct ProfileStore {
+    path: PathBuf,
+    data: StoreData,
+
+    /// Clave desbloqueada en esta ejecución, para no pedir la frase de paso dos veces
+    key: OnceCell<SecretKey>,
+}
+
+impl ProfileStore {
+    // Ruta del almacén: RDO_PROFILES o ~/.config/rdo/profiles.json
+    pub fn default_path() -> PathBuf {
+        Self::path_from(env::var("RDO_PROFILES").ok())
+    }
+
+    // Ruta del almacén según el valor de RDO_PROFILES, si está definida
+    fn path_from(rdo_profiles: Option<String>) -> PathBuf {
+        if let Some(path) = rdo_profiles {
+            return PathBuf::from(path);
+        }
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rdo")
+            .join("profiles.json")
+    }
+
+    // Abrir el almacén por defecto
+    pub fn open() -> Result<Self, io::Error> {
+        Self::open_at(Self::default_path())
+    }
+
+    // Abrir el almacén en una ruta concreta; si no existe se parte de uno vacío
+    pub fn open_at(path: PathBuf) -> Result<Self, io::Error> {
+        Self::open_importing(path, Path::new(LEGACY_PROFILE_FILE))
+    }
+
+    // Abrir el almacén; si no existe, se importa el perfil heredado `legacy` si lo hay
+    fn open_importing(path: PathBuf, legacy: &Path) -> Result<Self, io::Error> {
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            let data: StoreData = serde_json::from_str(&contents)?;
+            return Ok(Self { path, data, key: OnceCell::new() });
+        }
+
+        let mut store = Self { path, data: StoreData::default(), key: OnceCell::new() };
+
+        // Importar el profile.json heredado del directorio actual, si existe
+        if legacy.exists() {
+            let contents = fs::read_to_string(legacy)?;
+            let legacy: Profile = serde_json::from_str(&contents)?;
+            store.data.profiles.insert(LEGACY_PROFILE_NAME.to_string(), legacy);
+            store.data.default = Some(LEGACY_PROFILE_NAME.to_string());
+            store.save()?;
+            println!(
+                "Perfil de {} importado como '{}' en {}",
+                LEGACY_PROFILE_FILE, LEGACY_PROFILE_NAME, store.path.display()
+            );
+            if store.data.profiles[LEGACY_PROFILE_NAME].password.is_some() {
+                println!("Su contraseña sigue en claro en ambos archivos: cífrela con 'rdo profile rekey' y borre {}", LEGACY_PROFILE_FILE);
+            }
+        }
+
+        Ok(store)
+    }
+
+    // Guardar el almacén en disco, legible solo por el usuario
+    pub fn save(&self) -> Result<(), io::Error> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let json = serde_json::to_string_pretty(&self.data)?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // Los archivos de versiones anteriores se crearon con los permisos por defecto
+            if self.path.exists() {
+                fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options.open(&self.path)?.write_all(json.as_bytes())
+    }
+
+    // Clave de los secretos, desbloqueada la primera vez que hace falta
+    fn key(&self) -> Result<&SecretKey, RdoError> {
+        if let Some(key) = self.key.get() {
+            return Ok(key);
+        }
+        let encryption = self.data.encryption.as_ref()
+            .ok_or_else(|| RdoError::Config("No hay secretos cifrados en el archivo de perfiles. Cree uno con 'rdo profile rekey'".into()))?;
+        let key = encryption.unlock()?;
+        Ok(self.key.get_or_init(|| key))
+    }
+
+    // Clave de la sesión en hexadecimal, para RDO_SESSION_KEY
+    pub fn unlock(&self) -> Result<String, RdoError> {
+        Ok(self.key()?.to_hex())
+    }
+
+    // Cifrar la contraseña en claro de un perfil antes de guardarlo; la primera vez
+    // se elige la frase de paso del archivo
+    fn seal(&mut self, profile: &mut Profile) -> Result<(), RdoError> {
+        let Some(password) = profile.password.as_deref().filter(|p| !secret::is_encrypted(p)) else {
+            return Ok(());
+        };
+        if self.data.encryption.is_none() {
+            let (encryption, key) = Encryption::create(&secret::new_passphrase()?)?;
+            self.data.encryption = Some(encryption);
+            self.key = OnceCell::from(key);
+            eprintln!("Las contraseñas de {} se cifran con la frase de paso indicada", self.path.display());
+        }
+        profile.password = Some(self.key()?.encrypt(password));
+        Ok(())
+    }
+
+    // Descifrar la contraseña de un perfil ya combinado; las que vienen en claro
+    // (línea de comandos, entorno) se dejan como están
+    pub fn reveal(&self, profile: &mut Profile) -> Result<(), RdoError> {
+        if let Some(password) = profile.password.as_deref().filter(|p| secret::is_encrypted(p)) {
+            profile.password = Some(self.key()?.decrypt(password)?);
+        }
+        Ok(())
+    }
+
+    // Perfiles con la contraseña todavía en claro
+    pub fn plaintext_secrets(&self) -> Vec<&str> {
+        self.data.profiles.iter()
+            .filter(|(_, p)| p.password.as_deref().is_some_and(|p| !secret::is_encrypted(p)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    // Cifrar todas las contraseñas con una frase de paso nueva, incluidas las que estaban
+    // en claro; devuelve cuántas se cifraron
+    pub fn rekey(&mut self) -> Result<usize, RdoError> {
+        let mut passwords = Vec::new();
+        for (name, profile) in &self.data.profiles {
+            if let Some(password) = &profile.password {
+                let plain = if secret::is_encrypted(password) { self.key()?.decrypt(password)? } else { password.clone() };
+                passwords.push((name.clone(), plain));
+            }
+        }
+
+        let (encryption, key) = Encryption::create(&secret::new_passphrase()?)?;
+        for (name, plain) in &passwords {
+            if let Some(profile) = self.data.profiles.get_mut(name) {
+                profile.password = Some(key.encrypt(plain));
+            }
+        }
+        self.data.encryption = Some(encryption);
+        self.key = OnceCell::from(key);
+        Ok(passwords.len())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn default_name(&self) -> Option<&str> {
+        self.data.default.as_deref()
+    }
+
+    // Nombres de los perfiles guardados
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.data.profiles.keys()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.data.profiles.get(name)
+    }
+
+    // Nombre efectivo: el solicitado o, si no se indicó, el predeterminado
+    pub fn resolve_name(&self, requested: Option<&str>) -> Option<String> {
+        requested.or(self.default_name()).map(|s| s.to_string())
+    }
+
+    // Cargar el perfil solicitado o el predeterminado
+    pub fn load(&self, requested: Option<&str>) -> Result<Profile, io::Error> {
+        let name = self.resolve_name(requested).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No hay perfil predeterminado. Cree uno o use --profile <nombre>.",
+            )
+        })?;
+
+        self.get(&name).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No existe el perfil '{}'", name),
+            )
+        })
+    }
+
+    // Crear un perfil nuevo; el primero pasa a ser el predeterminado
+    pub fn create(&mut self, name: &str, mut profile: Profile) -> Result<(), RdoError> {
+        validate_name(name)?;
+        if self.data.profiles.contains_key(name) {
+            return Err(RdoError::Config(format!("El perfil '{}' ya existe. Use 'profile update'.", name)));
+        }
+        self.seal(&mut profile)?;
+        self.data.profiles.insert(name.to_string(), profile);
+        if self.data.default.is_none() {
+            self.data.default = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    // Actualizar los campos indicados de un perfil existente
+    pub fn update(&mut self, name: &str, changes: &Profile) -> Result<(), RdoError> {
+        if !self.data.profiles.contains_key(name) {
+            return Err(RdoError::Config(format!("No existe el perfil '{}'", name)));
+        }
+        let mut changes = changes.clone();
+        self.seal(&mut changes)?;
+        let profile = self.data.profiles.get_mut(name)
+            .ok_or_else(|| RdoError::Config(format!("No existe el perfil '{}'", name)))?;
+        profile.merge(&changes);
+        Ok(())
+    }
+
+    // Crear o reemplazar un perfil
+    pub fn upsert(&mut self, name: &str, mut profile: Profile) -> Result<(), RdoError> {
+        validate_name(name)?;
+        self.seal(&mut profile)?;
+        self.data.profiles.insert(name.to_string(), profile);
+        if self.data.default.is_none() {
+            self.data.default = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), RdoError> {
+        validate_name(to)?;
+        if self.data.profiles.contains_key(to) {
+            return Err(RdoError::Config(format!("El perfil '{}' ya existe", to)));
+        }
+        let profile = self.data.profiles.remove(from)
+            .ok_or_else(|| RdoError::Config(format!("No existe el perfil '{}'", from)))?;
+        self.data.profiles.insert(to.to_string(), profile);
+        if self.data.default.as_deref() == Some(from) {
+            self.data.default = Some(to.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), RdoError> {
+        if self.data.profiles.remove(name).is_none() {
+            return Err(RdoError::Config(format!("No existe el perfil '{}'", name)));
+        }
+        if self.data.default.as_deref() == Some(name) {
+            self.data.default = None;
+        }
+        Ok(())
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<(), RdoError> {
+        if !self.data.profiles.contains_key(name) {
+            return Err(RdoError::Config(format!("No existe el perfil '{}'", name)));
+        }
+        self.data.default = Some(name.to_string());
+        Ok(())
+    }
+}
+
+// Los nombres se usan en la línea de comandos, así que limitamos los caracteres
+fn validate_name(name: &str) -> Result<(), RdoError> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(RdoError::Config(format!(
+            "Nombre de perfil inválido '{}': use letras, números, '_', '-' o '.'",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::test_encryption;
+
+    // Almacén en un directorio temporal con la clave ya desbloqueada
+    fn unlocked_store(dir: &tempfile::TempDir) -> ProfileStore {
+        let (encryption, key) = test_encryption("frase");
+        ProfileStore {
+            path: dir.path().join("profiles.json"),
+            data: StoreData { encryption: Some(encryption), ..StoreData::default() },
+            key: OnceCell::from(key),
+        }
+    }
+
+    fn complete(host: &str) -> Profile {
+        Profile {
+            xhost: Some(host.into()),
+            port: Some(5432),
+            username: Some("odoo".into()),
+            container_id: Some("ctr".into()),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn profiles_are_created_updated_renamed_and_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ProfileStore::open_at(dir.path().join("profiles.json")).unwrap();
+
+        // El primero pasa a ser el predeterminado
+        store.create("prod", complete("db-prod")).unwrap();
+        store.create("dev", complete("db-dev")).unwrap();
+        assert_eq!(store.default_name(), Some("prod"));
+        assert!(matches!(store.create("dev", complete("otro")), Err(RdoError::Config(_))));
+
+        // update solo cambia los campos indicados
+        store.update("dev", &Profile { port: Some(5433), ..Profile::default() }).unwrap();
+        let dev = store.get("dev").unwrap();
+        assert_eq!((dev.xhost.as_deref(), dev.port), (Some("db-dev"), Some(5433)));
+        assert!(store.update("nope", &Profile::default()).is_err());
+
+        // Renombrar sobre un perfil existente no lo pisa; renombrar el predeterminado lo arrastra
+        assert!(matches!(store.rename("dev", "prod"), Err(RdoError::Config(_))));
+        assert_eq!(store.get("prod").unwrap().xhost.as_deref(), Some("db-prod"));
+        store.rename("prod", "main").unwrap();
+        assert_eq!(store.default_name(), Some("main"));
+        assert!(store.get("prod").is_none());
+
+        // Borrar el predeterminado deja el almacén sin predeterminado
+        store.delete("main").unwrap();
+        assert_eq!(store.default_name(), None);
+        assert!(store.delete("main").is_err());
+
+        assert!(store.set_default("nope").is_err());
+        store.set_default("dev").unwrap();
+        store.save().unwrap();
+
+        let reopened = ProfileStore::open_at(store.path().to_path_buf()).unwrap();
+        assert_eq!(reopened.default_name(), Some("dev"));
+        assert_eq!(reopened.names().collect::<Vec<_>>(), ["dev"]);
+    }
+
+    #[test]
+    fn profile_names_are_validated() {
+        for name in ["prod", "staging-2", "cliente_a.eu"] {
+            assert!(validate_name(name).is_ok(), "{}", name);
+        }
+        for name in ["", "con espacio", "../x", "a/b", "año"] {
+            assert!(validate_name(name).is_err(), "{}", name);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ProfileStore::open_at(dir.path().join("profiles.json")).unwrap();
+        assert!(store.create("a b", complete("db")).is_err());
+        assert!(store.upsert("a/b", complete("db")).is_err());
+        store.create("ok", complete("db")).unwrap();
+        assert!(store.rename("ok", "").is_err());
+    }
+
+    #[test]
+    fn profile_flag_picks_the_named_profile_or_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ProfileStore::open_at(dir.path().join("profiles.json")).unwrap();
+        let missing = store.load(None).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+
+        store.create("prod", complete("db-prod")).unwrap();
+        store.create("dev", complete("db-dev")).unwrap();
+        assert_eq!(store.load(None).unwrap().xhost.as_deref(), Some("db-prod"));
+        assert_eq!(store.load(Some("dev")).unwrap().xhost.as_deref(), Some("db-dev"));
+        assert_eq!(store.resolve_name(Some("dev")).as_deref(), Some("dev"));
+
+        let unknown = store.load(Some("nope")).unwrap_err();
+        assert!(unknown.to_string().contains("'nope'"), "{}", unknown);
+    }
+
+    #[test]
+    fn legacy_profile_is_imported_once_as_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join(LEGACY_PROFILE_FILE);
+        fs::write(&legacy, r#"{"xhost": "db", "port": 5432, "username": "odoo", "container_id": "ctr"}"#).unwrap();
+        let path = dir.path().join("rdo").join("profiles.json");
+
+        let store = ProfileStore::open_importing(path.clone(), &legacy).unwrap();
+        assert_eq!(store.default_name(), Some(LEGACY_PROFILE_NAME));
+        assert_eq!(store.get(LEGACY_PROFILE_NAME).unwrap().xhost.as_deref(), Some("db"));
+        assert!(path.exists());
+
+        // Con el almacén ya creado, el profile.json heredado no vuelve a leerse
+        fs::write(&legacy, r#"{"xhost": "otro"}"#).unwrap();
+        let reopened = ProfileStore::open_importing(path, &legacy).unwrap();
+        assert_eq!(reopened.get(LEGACY_PROFILE_NAME).unwrap().xhost.as_deref(), Some("db"));
+
+        let empty = ProfileStore::open_importing(dir.path().join("vacio.json"), &dir.path().join("no-existe.json")).unwrap();
+        assert_eq!(empty.names().count(), 0);
+    }
+
+    #[test]
+    fn store_path_comes_from_rdo_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("perfiles.json");
+        assert_eq!(ProfileStore::path_from(Some(path.to_string_lossy().into_owned())), path);
+        if let Some(config) = dirs::config_dir() {
+            assert_eq!(ProfileStore::path_from(None), config.join("rdo").join("profiles.json"));
+        }
+
+        // Sin profile.json heredado, el almacén de esa ruta empieza vacío y se crea al guardar
+        let legacy = dir.path().join(LEGACY_PROFILE_FILE);
+        let mut store = ProfileStore::open_importing(path.clone(), &legacy).unwrap();
+        assert_eq!(store.default_name(), None);
+        store.create("ci", complete("db")).unwrap();
+        store.save().unwrap();
+
+        assert!(path.exists());
+        assert_eq!(ProfileStore::open_importing(path, &legacy).unwrap().default_name(), Some("ci"));
+    }
+
+    #[test]
+    fn passwords_are_encrypted_on_disk_and_revealed_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = unlocked_store(&dir);
+        store.create("prod", Profile { password: Some("s3cr3t".into()), ..Profile::default() }).unwrap();
+        store.update("prod", &Profile { username: Some("odoo".into()), ..Profile::default() }).unwrap();
+        store.save().unwrap();
+
+        let contents = fs::read_to_string(store.path()).unwrap();
+        assert!(!contents.contains("s3cr3t"), "{}", contents);
+        assert!(store.plaintext_secrets().is_empty());
+
+        let mut reopened = ProfileStore::open_at(store.path().to_path_buf()).unwrap();
+        reopened.key = store.key.clone();
+        let mut profile = reopened.load(Some("prod")).unwrap();
+        reopened.reveal(&mut profile).unwrap();
+        assert_eq!(profile.password.as_deref(), Some("s3cr3t"));
+        assert_eq!(profile.username.as_deref(), Some("odoo"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(store.path()).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn printed_profiles_never_show_the_password() {
+        let profile = Profile { username: Some("odoo".into()), password: Some("s3cr3t".into()), ..Profile::default() };
+        let json = profile.to_masked_json();
+        assert!(!json.contains("s3cr3t"), "{}", json);
+        assert!(json.contains("\"password\": \"***\""), "{}", json);
+        assert!(json.contains("odoo"));
+
+        let sealed = Profile { password: Some("enc:v1:00ff".into()), ..Profile::default() };
+        assert!(!sealed.to_masked_json().contains("enc:v1"));
+        assert!(Profile::default().to_masked_json().contains("\"password\": null"));
+    }
+
+    #[test]
+    fn plaintext_passwords_from_the_command_line_are_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = unlocked_store(&dir);
+        let mut profile = Profile { password: Some("en claro".into()), ..Profile::default() };
+        store.reveal(&mut profile).unwrap();
+        assert_eq!(profile.password.as_deref(), Some("en claro"));
+    }
+}
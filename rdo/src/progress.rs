@@ -0,0 +1,55 @@
+use indicatif::{FormattedDuration, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::time::Duration;
+
+use crate::output::{self, Verbosity};
+
+/// Plantillas de la barra según lo que se mide y si se conoce el total
+const BYTES_TEMPLATE: &str = "{spinner} [{elapsed_precise}] [{bar:30}] {bytes}/{total_bytes} (quedan {eta}) {wide_msg}";
+const BYTES_UNKNOWN_TEMPLATE: &str = "{spinner} [{elapsed_precise}] {bytes} ({binary_bytes_per_sec}) {wide_msg}";
+const ENTRIES_TEMPLATE: &str = "{spinner} [{elapsed_precise}] [{bar:30}] {pos}/{len} elementos (quedan {eta}) {wide_msg}";
+const ENTRIES_UNKNOWN_TEMPLATE: &str = "{spinner} [{elapsed_precise}] {pos} elementos {wide_msg}";
+const ELAPSED_TEMPLATE: &str = "{spinner} [{elapsed_precise}] {wide_msg}";
+
+// Barra por bytes del dump consumidos; queda oculta hasta llamar a `start`
+pub fn bytes_bar(total: Option<u64>) -> ProgressBar {
+    let template = if total.is_some() { BYTES_TEMPLATE } else { BYTES_UNKNOWN_TEMPLATE };
+    new_bar(total, template)
+}
+
+// Barra por elementos del TOC que restaura pg_restore
+pub fn entries_bar(total: Option<u64>) -> ProgressBar {
+    let template = if total.is_some() { ENTRIES_TEMPLATE } else { ENTRIES_UNKNOWN_TEMPLATE };
+    new_bar(total, template)
+}
+
+// Solo el tiempo transcurrido, para herramientas que no informan de su avance
+pub fn spinner() -> ProgressBar {
+    new_bar(None, ELAPSED_TEMPLATE)
+}
+
+fn new_bar(total: Option<u64>, template: &str) -> ProgressBar {
+    let bar = ProgressBar::with_draw_target(total, ProgressDrawTarget::hidden());
+    let style = ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ");
+    bar.set_style(style);
+    bar
+}
+
+// Empezar a dibujar la barra en stderr (indicatif no dibuja si stderr no es una terminal)
+pub fn start(bar: &ProgressBar) {
+    if output::verbosity() == Verbosity::Quiet {
+        return;
+    }
+    bar.set_draw_target(ProgressDrawTarget::stderr());
+    bar.enable_steady_tick(Duration::from_millis(200));
+}
+
+// Quitar la barra y dejar el tiempo total de la restauración
+pub fn finish(bar: &ProgressBar) {
+    bar.finish_and_clear();
+    if output::verbosity() == Verbosity::Quiet {
+        return;
+    }
+    println!("Tiempo de restauración: {}", FormattedDuration(bar.elapsed()));
+}
@@ -0,0 +1,106 @@
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use crate::error::RdoError;
+
+/// Respuestas dadas de antemano con --yes, --no-input y --create-if-missing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Answers {
+    /// Responder "s" a todas las confirmaciones
+    pub yes: bool,
+    /// No preguntar nunca; lo que no esté respondido es un error
+    pub no_input: bool,
+    /// Crear la base de datos destino si no existe
+    pub create_if_missing: bool,
+}
+
+static ANSWERS: OnceLock<Answers> = OnceLock::new();
+
+pub fn init(answers: Answers) {
+    let _ = ANSWERS.set(answers);
+}
+
+fn answers() -> Answers {
+    ANSWERS.get().copied().unwrap_or_default()
+}
+
+// Solo se pregunta si está permitido y la entrada estándar es una terminal;
+// si no, se falla enseguida en lugar de quedarse esperando
+fn ensure_interactive(need: &str, hint: &str) -> Result<(), RdoError> {
+    let reason = if answers().no_input {
+        "se indicó --no-input"
+    } else if !stdin().is_terminal() {
+        "la entrada estándar no es una terminal"
+    } else {
+        return Ok(());
+    };
+    Err(RdoError::InputRequired(format!("Hace falta {}, pero {}. {}", need, reason, hint)))
+}
+
+// Si se puede preguntar al usuario
+pub fn can_ask() -> bool {
+    !answers().no_input && stdin().is_terminal()
+}
+
+// Leer una línea de la entrada estándar
+fn read_answer(question: &str) -> Result<String, RdoError> {
+    print!("{}", question);
+    stdout().flush()?;
+
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+// Pedir confirmación (s/n); --yes la responde de antemano
+pub fn confirm(question: &str) -> Result<bool, RdoError> {
+    if answers().yes {
+        println!("{} (s/n): s (--yes)", question);
+        return Ok(true);
+    }
+    ensure_interactive(&format!("responder \"{}\"", question), "Use --yes para confirmar sin preguntar")?;
+    Ok(read_answer(&format!("{} (s/n): ", question))?.to_lowercase() == "s")
+}
+
+// Confirmar una operación destructiva escribiendo `expected`; --yes la responde de antemano
+pub fn confirm_typed(question: &str, expected: &str) -> Result<bool, RdoError> {
+    if answers().yes {
+        println!("{}: {} (--yes)", question, expected);
+        return Ok(true);
+    }
+    ensure_interactive(&format!("escribir '{}' para confirmar", expected), "Use --yes para confirmar sin preguntar")?;
+    Ok(read_answer(&format!("{}: ", question))? == expected)
+}
+
+// Confirmar la creación de una base de datos que no existe; también la responde --create-if-missing
+pub fn confirm_create(question: &str) -> Result<bool, RdoError> {
+    if answers().create_if_missing {
+        println!("{} (s/n): s (--create-if-missing)", question);
+        return Ok(true);
+    }
+    if answers().yes {
+        return confirm(question);
+    }
+    ensure_interactive(&format!("responder \"{}\"", question), "Use --create-if-missing o --yes para crearla sin preguntar")?;
+    Ok(read_answer(&format!("{} (s/n): ", question))?.to_lowercase() == "s")
+}
+
+// Pedir un texto libre; la respuesta vacía se devuelve tal cual
+pub fn input(question: &str) -> Result<String, RdoError> {
+    ensure_interactive(&format!("responder \"{}\"", question.trim_end_matches([' ', ':'])), "Indíquelo con las opciones del comando")?;
+    read_answer(question)
+}
+
+// Pedir la frase de paso de los perfiles, sin eco
+pub fn passphrase(question: &str) -> Result<String, RdoError> {
+    ensure_interactive("la frase de paso de los perfiles", "Use RDO_SESSION_KEY (rdo profile unlock) o RDO_PASSPHRASE")?;
+    Ok(rpassword::prompt_password(question)?)
+}
+
+// Pedir la contraseña de un usuario; --yes no puede responderla
+pub fn password(username: &str) -> Result<String, RdoError> {
+    ensure_interactive(&format!("la contraseña del usuario '{}'", username), "Use PGPASSWORD, ~/.pgpass o --password-command")?;
+    println!("Se requiere contraseña para el usuario '{}'", username);
+    // Sin eco en la terminal
+    Ok(rpassword::prompt_password("Ingrese la contraseña: ")?)
+}
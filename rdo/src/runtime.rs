@@ -0,0 +1,264 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::profile::Profile;
+
+/// Programa con que se ejecutan psql, pg_restore, pg_dump y las órdenes sobre los backups
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    /// `docker exec` en el contenedor
+    #[default]
+    Docker,
+    /// `podman exec` (también sin root)
+    Podman,
+    /// `nerdctl exec` (containerd)
+    Nerdctl,
+    /// Directamente en el host, sin contenedor: PostgreSQL se alcanza por red y los backups son rutas locales
+    Local,
+}
+
+impl Runtime {
+    // Ejecutor de las órdenes para este runtime
+    pub fn executor(self) -> Arc<dyn ContainerRuntime> {
+        match self {
+            Runtime::Docker => Arc::new(ExecRuntime { program: "docker" }),
+            Runtime::Podman => Arc::new(ExecRuntime { program: "podman" }),
+            Runtime::Nerdctl => Arc::new(ExecRuntime { program: "nerdctl" }),
+            Runtime::Local => Arc::new(LocalRuntime),
+        }
+    }
+}
+
+impl fmt::Display for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Runtime::Docker => write!(f, "docker"),
+            Runtime::Podman => write!(f, "podman"),
+            Runtime::Nerdctl => write!(f, "nerdctl"),
+            Runtime::Local => write!(f, "local"),
+        }
+    }
+}
+
+// Para leer el runtime de RDO_RUNTIME con los mismos nombres que --runtime
+impl FromStr for Runtime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Runtime as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// Ejecuta órdenes dentro de un contenedor (o en el host, en modo local).
+/// Solo construye el `Command`: quien llama decide si lo ejecuta, lo lanza con tuberías
+/// o lee su salida, igual que con cualquier otro proceso.
+pub trait ContainerRuntime: fmt::Debug + Send + Sync {
+    // Orden que ejecuta `argv` en `container` con las variables `env`.
+    // Con `stdin`, la entrada estándar llega al proceso del contenedor
+    fn command(&self, container: &str, argv: &[&str], env: &[(&str, &str)], stdin: bool) -> Command;
+
+    // Orden que lista los contenedores en marcha que cumplen todos los filtros de `ps --filter`,
+    // uno por línea como "<id>\t<nombre>"; `None` si el runtime no usa contenedores
+    fn ps(&self, filters: &[String]) -> Option<Command>;
+
+    // Orden que describe los contenedores en JSON, como `docker inspect`
+    fn inspect(&self, containers: &[&str]) -> Option<Command>;
+}
+
+/// Runtimes con la interfaz de `docker exec`: docker, podman y nerdctl
+#[derive(Debug)]
+struct ExecRuntime {
+    program: &'static str,
+}
+
+impl ContainerRuntime for ExecRuntime {
+    fn command(&self, container: &str, argv: &[&str], env: &[(&str, &str)], stdin: bool) -> Command {
+        let mut cmd = Command::new(self.program);
+        cmd.arg("exec");
+        if stdin {
+            cmd.arg("-i");
+        }
+        // Solo el nombre va en la línea de órdenes (visible en `ps`); el valor lo hereda
+        // `exec` del entorno de su propio proceso
+        for (name, value) in env {
+            cmd.args(["-e", name]).env(name, value);
+        }
+        cmd.arg(container).args(argv);
+        cmd
+    }
+
+    fn ps(&self, filters: &[String]) -> Option<Command> {
+        let mut cmd = Command::new(self.program);
+        cmd.arg("ps");
+        for filter in filters {
+            cmd.args(["--filter", filter]);
+        }
+        cmd.args(["--format", "{{.ID}}\t{{.Names}}"]);
+        Some(cmd)
+    }
+
+    fn inspect(&self, containers: &[&str]) -> Option<Command> {
+        let mut cmd = Command::new(self.program);
+        cmd.arg("inspect").args(containers);
+        Some(cmd)
+    }
+}
+
+/// Sin contenedor: las órdenes se ejecutan en el host y `container` se ignora
+#[derive(Debug)]
+struct LocalRuntime;
+
+impl ContainerRuntime for LocalRuntime {
+    fn command(&self, _container: &str, argv: &[&str], env: &[(&str, &str)], stdin: bool) -> Command {
+        let (program, args) = argv.split_first().expect("orden vacía");
+        let mut cmd = Command::new(program);
+        cmd.args(args).envs(env.iter().copied());
+        if !stdin {
+            cmd.stdin(Stdio::null());
+        }
+        cmd
+    }
+
+    fn ps(&self, _filters: &[String]) -> Option<Command> {
+        None
+    }
+
+    fn inspect(&self, _containers: &[&str]) -> Option<Command> {
+        None
+    }
+}
+
+impl Profile {
+    // Orden en un contenedor con el ejecutor del perfil
+    pub fn exec<S: AsRef<str>>(&self, container: &str, argv: &[S], stdin: bool) -> Command {
+        let argv: Vec<&str> = argv.iter().map(|a| a.as_ref()).collect();
+        self.executor().command(container, &argv, &[], stdin)
+    }
+
+    // Orden de PostgreSQL (psql, pg_restore, pg_dump), con PGPASSWORD si hay contraseña
+    pub fn pg_command<S: AsRef<str>>(&self, container: &str, argv: &[S], stdin: bool) -> Command {
+        let argv: Vec<&str> = argv.iter().map(|a| a.as_ref()).collect();
+        let env: Vec<(&str, &str)> = self.password.iter().map(|p| ("PGPASSWORD", p.as_str())).collect();
+        self.executor().command(container, &argv, &env, stdin)
+    }
+}
+
+#[cfg(test)]
+pub use fake::FakeRuntime;
+
+#[cfg(test)]
+mod fake {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Respuesta a una orden: código de salida, stdout y stderr
+    pub type Reply = (i32, String, String);
+
+    type Responder = Box<dyn Fn(&[&str]) -> Reply + Send + Sync>;
+
+    /// Ejecutor para las pruebas: anota cada orden y responde con lo que decida `responder`,
+    /// mediante un `sh` local que imprime la respuesta y termina con su código
+    pub struct FakeRuntime {
+        pub calls: Mutex<Vec<Vec<String>>>,
+        responder: Responder,
+    }
+
+    impl FakeRuntime {
+        pub fn new(responder: impl Fn(&[&str]) -> Reply + Send + Sync + 'static) -> Arc<Self> {
+            Arc::new(Self { calls: Mutex::new(Vec::new()), responder: Box::new(responder) })
+        }
+
+        // Órdenes recibidas, con el contenedor y las variables de entorno delante
+        pub fn calls(&self) -> Vec<Vec<String>> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl fmt::Debug for FakeRuntime {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("FakeRuntime").field("calls", &self.calls).finish()
+        }
+    }
+
+    impl ContainerRuntime for FakeRuntime {
+        fn command(&self, container: &str, argv: &[&str], env: &[(&str, &str)], _stdin: bool) -> Command {
+            let mut call = vec![container.to_string()];
+            call.extend(env.iter().map(|(name, value)| format!("{}={}", name, value)));
+            call.extend(argv.iter().map(|a| a.to_string()));
+            self.calls.lock().unwrap().push(call);
+
+            self.reply(argv)
+        }
+
+        fn ps(&self, filters: &[String]) -> Option<Command> {
+            let mut call = vec!["ps".to_string()];
+            call.extend(filters.iter().cloned());
+            self.calls.lock().unwrap().push(call.clone());
+
+            let argv: Vec<&str> = call.iter().map(|a| a.as_str()).collect();
+            Some(self.reply(&argv))
+        }
+
+        fn inspect(&self, containers: &[&str]) -> Option<Command> {
+            let mut call = vec!["inspect"];
+            call.extend(containers);
+            self.calls.lock().unwrap().push(call.iter().map(|a| a.to_string()).collect());
+            Some(self.reply(&call))
+        }
+    }
+
+    impl FakeRuntime {
+        // `sh` que imprime la respuesta a `argv` y termina con su código
+        fn reply(&self, argv: &[&str]) -> Command {
+            let (code, stdout, stderr) = (self.responder)(argv);
+            // Los argumentos no pueden llevar NUL (salidas de `find -print0`): stdout va con
+            // los escapes de `printf %b`
+            let stdout = stdout.replace('\\', "\\\\").replace('\0', "\\0000");
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", "printf '%b' \"$1\"; printf '%s' \"$2\" >&2; exit \"$3\"", "fake", &stdout, &stderr, &code.to_string()]);
+            cmd
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(cmd: &Command) -> Vec<String> {
+        cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn exec_runtimes_share_the_docker_exec_interface_and_keep_secrets_off_argv() {
+        for (runtime, program) in [(Runtime::Docker, "docker"), (Runtime::Podman, "podman"), (Runtime::Nerdctl, "nerdctl")] {
+            let cmd = runtime.executor().command("db", &["psql", "-c", "SELECT 1"], &[("PGPASSWORD", "x")], true);
+            assert_eq!(cmd.get_program(), program);
+            assert_eq!(argv(&cmd), ["exec", "-i", "-e", "PGPASSWORD", "db", "psql", "-c", "SELECT 1"]);
+            let env: Vec<_> = cmd.get_envs().collect();
+            assert_eq!(env, [(std::ffi::OsStr::new("PGPASSWORD"), Some(std::ffi::OsStr::new("x")))]);
+        }
+    }
+
+    #[test]
+    fn local_runtime_runs_on_the_host_with_env_on_the_process() {
+        let cmd = Runtime::Local.executor().command("ignorado", &["psql", "-c", "SELECT 1"], &[("PGPASSWORD", "x")], false);
+        assert_eq!(cmd.get_program(), "psql");
+        assert_eq!(argv(&cmd), ["-c", "SELECT 1"]);
+        let env: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(env, [(std::ffi::OsStr::new("PGPASSWORD"), Some(std::ffi::OsStr::new("x")))]);
+    }
+
+    #[test]
+    fn runtime_names_round_trip() {
+        for runtime in Runtime::value_variants() {
+            assert_eq!(runtime.to_string().parse::<Runtime>(), Ok(*runtime));
+        }
+        assert!("kubectl".parse::<Runtime>().is_err());
+    }
+}
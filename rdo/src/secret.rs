@@ -0,0 +1,254 @@
+// Cifrado de los secretos del almacén de perfiles. La clave se deriva de una frase de paso
+// con Argon2id y cada secreto se cifra con XChaCha20-Poly1305 y un nonce aleatorio.
+// En el archivo solo quedan la sal, los parámetros de Argon2 y un valor de comprobación
+// para saber si la frase de paso es la correcta.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+
+use crate::error::RdoError;
+use crate::prompt;
+
+/// Prefijo de los valores cifrados en el archivo de perfiles
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Clave ya derivada, en hexadecimal, tal como la imprime `rdo profile unlock`
+pub const SESSION_KEY_ENV: &str = "RDO_SESSION_KEY";
+
+/// Frase de paso, para CI y scripts
+pub const PASSPHRASE_ENV: &str = "RDO_PASSPHRASE";
+
+/// Frase de paso nueva para `rdo profile rekey` sin terminal
+pub const NEW_PASSPHRASE_ENV: &str = "RDO_NEW_PASSPHRASE";
+
+/// Texto que se cifra como comprobación de la clave
+const CHECK_PLAINTEXT: &str = "rdo";
+
+const KDF_ARGON2ID: &str = "argon2id";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Cabecera de cifrado guardada en el archivo de perfiles
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Encryption {
+    /// Función de derivación de la clave
+    pub kdf: String,
+
+    /// Sal de Argon2, en hexadecimal
+    pub salt: String,
+
+    /// Memoria de Argon2 en KiB
+    pub m_cost: u32,
+
+    /// Iteraciones de Argon2
+    pub t_cost: u32,
+
+    /// Paralelismo de Argon2
+    pub p_cost: u32,
+
+    /// CHECK_PLAINTEXT cifrado con la clave
+    pub check: String,
+}
+
+/// Clave de cifrado derivada de la frase de paso
+#[derive(Clone)]
+pub struct SecretKey([u8; KEY_LEN]);
+
+// Nunca se muestra la clave en los mensajes de depuración
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(********)")
+    }
+}
+
+impl SecretKey {
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    fn from_hex(value: &str) -> Option<Self> {
+        from_hex(value.trim())?.try_into().ok().map(SecretKey)
+    }
+
+    // Cifrar un secreto: enc:v1:<nonce y texto cifrado en hexadecimal>
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let cipher = XChaCha20Poly1305::new(&self.0.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, plaintext.as_bytes()).expect("cifrado en memoria");
+        let mut bytes = nonce.to_vec();
+        bytes.extend(ciphertext);
+        format!("{}{}", ENCRYPTED_PREFIX, to_hex(&bytes))
+    }
+
+    // Descifrar un valor cifrado con `encrypt`; falla si la clave no es la suya o está alterado
+    pub fn decrypt(&self, value: &str) -> Result<String, RdoError> {
+        let invalid = || RdoError::Config("No se pudo descifrar el secreto: la clave no corresponde o el valor está dañado".into());
+        let bytes = value.strip_prefix(ENCRYPTED_PREFIX).and_then(from_hex).ok_or_else(invalid)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let cipher = XChaCha20Poly1305::new(&self.0.into());
+        let plaintext = cipher.decrypt(XNonce::from_slice(nonce), ciphertext).map_err(|_| invalid())?;
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+}
+
+// Si un valor del archivo está cifrado
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+impl Encryption {
+    // Cabecera nueva, con sal aleatoria y los parámetros recomendados de Argon2id
+    pub fn create(passphrase: &str) -> Result<(Self, SecretKey), RdoError> {
+        Self::create_with(passphrase, Params::default())
+    }
+
+    fn create_with(passphrase: &str, params: Params) -> Result<(Self, SecretKey), RdoError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut encryption = Encryption {
+            kdf: KDF_ARGON2ID.to_string(),
+            salt: to_hex(&salt),
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+            check: String::new(),
+        };
+        let key = encryption.derive(passphrase)?;
+        encryption.check = key.encrypt(CHECK_PLAINTEXT);
+        Ok((encryption, key))
+    }
+
+    fn derive(&self, passphrase: &str) -> Result<SecretKey, RdoError> {
+        if self.kdf != KDF_ARGON2ID {
+            return Err(RdoError::Config(format!("Derivación de clave desconocida en el archivo de perfiles: {}", self.kdf)));
+        }
+        let salt = from_hex(&self.salt).ok_or_else(|| RdoError::Config("Sal inválida en el archivo de perfiles".into()))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|e| RdoError::Config(format!("Parámetros de Argon2 inválidos: {}", e)))?;
+        let mut key = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| RdoError::Config(format!("No se pudo derivar la clave: {}", e)))?;
+        Ok(SecretKey(key))
+    }
+
+    // Clave a partir de una frase de paso, comprobando que es la de este archivo
+    pub fn with_passphrase(&self, passphrase: &str) -> Result<SecretKey, RdoError> {
+        let key = self.derive(passphrase)?;
+        if !self.accepts(&key) {
+            return Err(RdoError::Auth("Frase de paso incorrecta para los secretos de los perfiles".into()));
+        }
+        Ok(key)
+    }
+
+    fn accepts(&self, key: &SecretKey) -> bool {
+        key.decrypt(&self.check).is_ok_and(|check| check == CHECK_PLAINTEXT)
+    }
+
+    // Desbloquear la clave de la sesión: RDO_SESSION_KEY, RDO_PASSPHRASE o la frase de paso
+    // preguntada en la terminal
+    pub fn unlock(&self) -> Result<SecretKey, RdoError> {
+        if let Ok(value) = env::var(SESSION_KEY_ENV) {
+            if !value.is_empty() {
+                return match SecretKey::from_hex(&value) {
+                    Some(key) if self.accepts(&key) => Ok(key),
+                    _ => Err(RdoError::Auth(format!("{} no corresponde a este archivo de perfiles; ejecute de nuevo 'rdo profile unlock'", SESSION_KEY_ENV))),
+                };
+            }
+        }
+        if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+            if !passphrase.is_empty() {
+                return self.with_passphrase(&passphrase);
+            }
+        }
+        let passphrase = prompt::passphrase("Frase de paso de los perfiles: ")?;
+        self.with_passphrase(&passphrase)
+    }
+}
+
+// Frase de paso nueva: RDO_NEW_PASSPHRASE, RDO_PASSPHRASE o preguntada dos veces en la terminal
+pub fn new_passphrase() -> Result<String, RdoError> {
+    for var in [NEW_PASSPHRASE_ENV, PASSPHRASE_ENV] {
+        if let Ok(passphrase) = env::var(var) {
+            if !passphrase.is_empty() {
+                return Ok(passphrase);
+            }
+        }
+    }
+    loop {
+        let passphrase = prompt::passphrase("Nueva frase de paso para cifrar las contraseñas de los perfiles: ")?;
+        if passphrase.is_empty() {
+            eprintln!("La frase de paso no puede estar vacía");
+            continue;
+        }
+        if prompt::passphrase("Repita la frase de paso: ")? == passphrase {
+            return Ok(passphrase);
+        }
+        eprintln!("Las frases de paso no coinciden");
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len()).step_by(2).map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+pub fn test_encryption(passphrase: &str) -> (Encryption, SecretKey) {
+    // Argon2 mínimo: las pruebas no necesitan resistir fuerza bruta
+    Encryption::create_with(passphrase, Params::new(8, 1, 1, None).unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_and_never_repeat_the_ciphertext() {
+        let (_, key) = test_encryption("frase");
+        let first = key.encrypt("pa ss'; word");
+        let second = key.encrypt("pa ss'; word");
+        assert!(is_encrypted(&first));
+        assert!(!first.contains("pa ss"));
+        assert_ne!(first, second);
+        assert_eq!(key.decrypt(&first).unwrap(), "pa ss'; word");
+    }
+
+    #[test]
+    fn wrong_passphrase_or_tampered_value_is_rejected() {
+        let (encryption, key) = test_encryption("frase");
+        assert!(encryption.with_passphrase("frase").is_ok());
+        assert!(matches!(encryption.with_passphrase("otra"), Err(RdoError::Auth(_))));
+
+        let mut tampered = key.encrypt("secreto");
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == '0' { '1' } else { '0' });
+        assert!(key.decrypt(&tampered).is_err());
+
+        let (_, other) = test_encryption("frase");
+        assert!(other.decrypt(&key.encrypt("secreto")).is_err());
+    }
+
+    #[test]
+    fn session_key_survives_hex() {
+        let (encryption, key) = test_encryption("frase");
+        let restored = SecretKey::from_hex(&key.to_hex()).unwrap();
+        assert!(encryption.accepts(&restored));
+        assert!(SecretKey::from_hex("abc").is_none());
+    }
+}
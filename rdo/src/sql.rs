@@ -0,0 +1,118 @@
+// Citado de identificadores y literales para las órdenes SQL que rdo envía con `psql -c`.
+// `-c` no interpola variables de psql (`:'nombre'`), así que los valores se citan aquí,
+// con las mismas reglas que `quote_ident` y `quote_literal` de PostgreSQL.
+
+// Identificador entre comillas dobles, con las comillas internas dobladas
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Literal de texto entre comillas simples; si lleva barras invertidas se usa E'...'
+// para que el resultado no dependa de standard_conforming_strings
+pub fn quote_literal(value: &str) -> String {
+    let quoted = value.replace('\'', "''");
+    if value.contains('\\') {
+        format!("E'{}'", quoted.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", quoted)
+    }
+}
+
+// Valor de --dbname para psql, pg_dump y pg_restore. libpq toma un --dbname con '=' o que
+// empiece por postgresql:// como cadena de conexión completa, que podría llevar otro host;
+// por eso el nombre va siempre como `dbname='...'`, con \ y ' escapados con barra invertida
+pub fn conninfo_dbname(name: &str) -> String {
+    format!("dbname='{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+// Nombres que rompían las órdenes construidas con format! y `bash -c`
+pub const HOSTILE_NAMES: &[&str] = &[
+    "simple",
+    "con espacio",
+    "comilla'simple",
+    "comilla\"doble",
+    "x\"; DROP DATABASE postgres; --",
+    "x'; DROP DATABASE postgres; --",
+    "$(touch rdo-pwned)",
+    "`id`",
+    "a;b|c&d",
+    "barra\\invertida",
+    "-empieza-con-guion",
+    "ñandú",
+    "host=evil dbname=x",
+    "postgresql://otro/db",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deshacer el citado de un identificador, como haría el servidor
+    fn unquote_ident(quoted: &str) -> String {
+        let inner = quoted.strip_prefix('"').and_then(|s| s.strip_suffix('"')).expect("sin comillas dobles");
+        assert!(!inner.replace("\"\"", "").contains('"'), "comilla doble sin doblar en {}", quoted);
+        inner.replace("\"\"", "\"")
+    }
+
+    // Deshacer el citado de un literal ('...' o E'...')
+    fn unquote_literal(quoted: &str) -> String {
+        let (escaped, rest) = match quoted.strip_prefix('E') {
+            Some(rest) => (true, rest),
+            None => (false, quoted),
+        };
+        let inner = rest.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')).expect("sin comillas simples");
+        assert!(!inner.replace("''", "").contains('\''), "comilla simple sin doblar en {}", quoted);
+        let value = inner.replace("''", "'");
+        if escaped {
+            assert!(!value.replace("\\\\", "").contains('\\'), "barra invertida sin doblar en {}", quoted);
+            value.replace("\\\\", "\\")
+        } else {
+            value
+        }
+    }
+
+    #[test]
+    fn quote_ident_round_trips_hostile_names() {
+        for name in HOSTILE_NAMES {
+            assert_eq!(unquote_ident(&quote_ident(name)), *name);
+        }
+    }
+
+    #[test]
+    fn quote_literal_round_trips_hostile_names() {
+        for name in HOSTILE_NAMES {
+            assert_eq!(unquote_literal(&quote_literal(name)), *name);
+        }
+    }
+
+    // Deshacer el citado de `conninfo_dbname`, como haría libpq
+    fn unquote_conninfo(conninfo: &str) -> String {
+        let inner = conninfo.strip_prefix("dbname='").and_then(|s| s.strip_suffix('\'')).expect("sin dbname='...'");
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.push(chars.next().expect("barra invertida al final")),
+                '\'' => panic!("comilla simple sin escapar en {}", conninfo),
+                c => value.push(c),
+            }
+        }
+        value
+    }
+
+    #[test]
+    fn conninfo_dbname_round_trips_hostile_names() {
+        for name in HOSTILE_NAMES {
+            assert_eq!(unquote_conninfo(&conninfo_dbname(name)), *name);
+        }
+        assert_eq!(conninfo_dbname("host=evil dbname=x"), "dbname='host=evil dbname=x'");
+        assert_eq!(conninfo_dbname("a'b\\c"), "dbname='a\\'b\\\\c'");
+    }
+
+    #[test]
+    fn quote_literal_uses_escape_string_only_with_backslashes() {
+        assert_eq!(quote_literal("o'neil"), "'o''neil'");
+        assert_eq!(quote_literal("a\\b"), "E'a\\\\b'");
+    }
+}
@@ -0,0 +1,493 @@
+// Plantillas de ruta de los backups. Sin `path_template`, cada backup es una carpeta
+// <dir_backup>/<namedb>/ con un dump.*; con ella, los dumps están donde diga la plantilla,
+// por ejemplo {dir}/{name}/{date}/{name}.{ext} o {dir}/{name}_{date:%Y%m%d}.{ext}.
+// El listado, la restauración y `rdo backup` usan la misma plantilla. Aquí están también
+// la plantilla del nombre de la base de datos destino de `rdo restore --from` y la lectura
+// de las fechas escritas en los nombres de los backups.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+use crate::db::check_db_name;
+use crate::error::RdoError;
+use crate::profile::Profile;
+
+/// Formato de {date} si la plantilla no indica otro
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Nombre de la base de datos destino de `rdo restore --from` sin --to
+pub const DEFAULT_TARGET_TEMPLATE: &str = "{backup}_{date}";
+
+/// Formato de {date} en el nombre de la base de datos destino: la fecha de la restauración
+const TARGET_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Fechas que se reconocen en el nombre de un backup, de la más a la menos precisa. Con el año
+/// al final, primero va el mes, como en produccion_04_28_2025
+const NAME_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d_%H-%M-%S", "%Y%m%d_%H%M%S", "%Y-%m-%d", "%Y_%m_%d", "%Y%m%d", "%m_%d_%Y", "%m-%d-%Y",
+];
+
+/// Tramo de una plantilla
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    /// {dir}: dir_backup del perfil
+    Dir,
+    /// {name}: nombre del backup, el que se pasa a `rdo restore`
+    Name,
+    /// {date} o {date:<formato strftime>}
+    Date(String),
+    /// {ext}: extensión del dump, como sql, dump o sql.gz
+    Ext,
+    /// {backup}: en el nombre de la base de datos destino, el del backup restaurado
+    Backup,
+    /// `*`: cualquier texto sin '/'
+    Any,
+    /// `?`: un carácter que no sea '/'
+    One,
+}
+
+/// Plantilla de ruta ya comprobada
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+/// Plantilla del nombre de la base de datos destino, como `{backup}_{date}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetTemplate {
+    parts: Vec<Part>,
+}
+
+/// Valores de una ruta que coincide con la plantilla
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Captures {
+    pub name: String,
+    pub date: Option<NaiveDateTime>,
+    pub ext: Option<String>,
+}
+
+/// Búsqueda de los dumps de una plantilla con `find <root> -mindepth <depth> -maxdepth <depth> -path <pattern>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    pub root: String,
+    pub depth: usize,
+    pub pattern: String,
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Profile {
+    // Plantilla de ruta del perfil, si tiene una
+    pub fn path_template(&self) -> Result<Option<PathTemplate>, RdoError> {
+        self.path_template.as_deref().map(PathTemplate::parse).transpose()
+    }
+
+    // Plantilla del nombre de la base de datos destino; sin ella, DEFAULT_TARGET_TEMPLATE
+    pub fn target_template(&self) -> Result<TargetTemplate, RdoError> {
+        TargetTemplate::parse(self.target_template.as_deref().unwrap_or(DEFAULT_TARGET_TEMPLATE))
+    }
+
+    // Comprobar las plantillas antes de guardar el perfil
+    pub fn check_templates(&self) -> Result<(), RdoError> {
+        self.path_template()?;
+        self.target_template()?;
+        Ok(())
+    }
+
+    // Base de datos destino de `backup` cuando no se indica --to. Con plantilla de ruta,
+    // `backup` puede ser la ruta de un dump y {backup} es su {name}
+    pub fn default_target(&self, backup: &str) -> Result<String, RdoError> {
+        let name = match (self.path_template()?, &self.dir_backup) {
+            (Some(template), Some(dir_backup)) if backup.contains('/') => template.matches(dir_backup, backup)
+                .map(|captures| captures.name)
+                .unwrap_or_else(|| backup.rsplit('/').next().unwrap_or(backup).to_string()),
+            _ => backup.to_string(),
+        };
+        Ok(self.target_template()?.render(&name, Local::now().naive_local()))
+    }
+}
+
+impl TargetTemplate {
+    pub fn parse(template: &str) -> Result<Self, RdoError> {
+        let invalid = |reason: String| RdoError::Config(format!("Plantilla de base de datos destino inválida '{}': {}", template, reason));
+        let parts = scan(template, &["backup", "date"], TARGET_DATE_FORMAT).map_err(invalid)?;
+        if parts.iter().any(|p| matches!(p, Part::Any | Part::One)) {
+            return Err(invalid("no admite comodines".into()));
+        }
+        Ok(Self { parts })
+    }
+
+    // Nombre de la base de datos destino para el backup `backup` restaurado en `now`
+    pub fn render(&self, backup: &str, now: NaiveDateTime) -> String {
+        self.parts.iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Backup => backup.to_string(),
+                Part::Date(format) => now.format(format).to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+}
+
+// Separar una plantilla en tramos. `allowed` son los marcadores que admite y `date_format`,
+// el formato de {date} cuando no se indica otro
+fn scan(template: &str, allowed: &[&str], date_format: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        let part = match c {
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(format!("falta '}}' tras {{{}", placeholder)),
+                    }
+                }
+                let (key, format) = match placeholder.split_once(':') {
+                    Some((key, format)) => (key, Some(format)),
+                    None => (placeholder.as_str(), None),
+                };
+                if !allowed.contains(&key) || (format.is_some() && key != "date") {
+                    let names: Vec<String> = allowed.iter().map(|a| format!("{{{}}}", a)).collect();
+                    let (last, rest) = names.split_last().expect("hay marcadores");
+                    return Err(format!("marcador desconocido {{{}}}; use {} o {}", placeholder, rest.join(", "), last));
+                }
+                match key {
+                    "dir" => Part::Dir,
+                    "name" => Part::Name,
+                    "ext" => Part::Ext,
+                    "backup" => Part::Backup,
+                    _ => {
+                        let format = format.unwrap_or(date_format);
+                        if format.is_empty() || format.contains('/') || StrftimeItems::new(format).any(|i| i == Item::Error) {
+                            return Err(format!("formato de fecha '{}' inválido", format));
+                        }
+                        Part::Date(format.to_string())
+                    }
+                }
+            }
+            '*' => Part::Any,
+            '?' => Part::One,
+            c => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(part);
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> Result<Self, RdoError> {
+        let invalid = |reason: String| RdoError::Config(format!("Plantilla de ruta inválida '{}': {}", template, reason));
+        let parts = scan(template, &["dir", "name", "date", "ext"], DEFAULT_DATE_FORMAT).map_err(invalid)?;
+        if !parts.contains(&Part::Name) {
+            return Err(invalid("debe incluir {name}".into()));
+        }
+        Ok(Self { source: template.to_string(), parts })
+    }
+
+    // Tramos con {dir} y, si se indica, {name} ya sustituidos
+    fn expand(&self, dir: &str, name: Option<&str>) -> Vec<Part> {
+        // Sin '/' final, para no duplicarla en {dir}/...
+        let dir = match dir.trim_end_matches('/') {
+            "" => "/",
+            dir => dir,
+        };
+        self.parts.iter()
+            .map(|part| match (part, name) {
+                (Part::Dir, _) => Part::Literal(dir.to_string()),
+                (Part::Name, Some(name)) => Part::Literal(name.to_string()),
+                (part, _) => part.clone(),
+            })
+            .collect()
+    }
+
+    // Búsqueda con `find` de los dumps de la plantilla; con `name`, solo los de ese backup
+    pub fn search(&self, dir: &str, name: Option<&str>) -> Result<Search, RdoError> {
+        let mut pattern = String::new();
+        // Parte fija del principio de la ruta, hasta el primer comodín
+        let mut prefix = String::new();
+        let mut fixed = true;
+
+        for part in self.expand(dir, name) {
+            match part {
+                Part::Literal(text) => {
+                    pattern.push_str(&glob_escape(&text));
+                    if fixed {
+                        prefix.push_str(&text);
+                    }
+                }
+                Part::One => {
+                    pattern.push('?');
+                    fixed = false;
+                }
+                _ => {
+                    pattern.push('*');
+                    fixed = false;
+                }
+            }
+        }
+
+        if !prefix.starts_with('/') {
+            return Err(RdoError::Config(format!("La plantilla de ruta '{}' debe dar una ruta absoluta; revise dir_backup", self)));
+        }
+        // `find` empieza en el directorio más profundo que no depende de la búsqueda
+        let root = match &prefix[..prefix.rfind('/').unwrap_or(0)] {
+            "" => "/".to_string(),
+            root => root.to_string(),
+        };
+        let slashes = pattern.matches('/').count();
+        let depth = if root == "/" { slashes } else { slashes - root.matches('/').count() };
+        Ok(Search { root, depth, pattern })
+    }
+
+    // Valores de `path` si coincide con la plantilla
+    pub fn matches(&self, dir: &str, path: &str) -> Option<Captures> {
+        let mut state = State::default();
+        if !match_parts(&self.expand(dir, None), path, &mut state) {
+            return None;
+        }
+        Some(Captures { name: state.name?, date: state.date, ext: state.ext })
+    }
+
+    // Ruta de un backup nuevo; no se puede si la plantilla tiene comodines
+    pub fn render(&self, dir: &str, name: &str, date: NaiveDateTime, ext: &str) -> Result<String, RdoError> {
+        check_db_name(name)?;
+        let mut path = String::new();
+        for part in self.expand(dir, Some(name)) {
+            match part {
+                Part::Literal(text) => path.push_str(&text),
+                Part::Date(format) => path.push_str(&date.format(&format).to_string()),
+                Part::Ext => path.push_str(ext),
+                _ => return Err(RdoError::Config(format!(
+                    "La plantilla de ruta '{}' tiene comodines; no se sabe qué nombre dar al backup nuevo", self
+                ))),
+            }
+        }
+        Ok(path)
+    }
+}
+
+/// Valores capturados mientras se compara una ruta
+#[derive(Debug, Clone, Default)]
+struct State {
+    name: Option<String>,
+    date: Option<NaiveDateTime>,
+    ext: Option<String>,
+}
+
+// Comparar `text` con los tramos; los marcadores prueban del valor más corto al más largo,
+// y si un marcador se repite debe valer lo mismo
+fn match_parts(parts: &[Part], text: &str, state: &mut State) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return text.is_empty();
+    };
+    match part {
+        Part::Literal(literal) => text.strip_prefix(literal.as_str()).is_some_and(|text| match_parts(rest, text, state)),
+        Part::One => match text.chars().next() {
+            Some(c) if c != '/' => match_parts(rest, &text[c.len_utf8()..], state),
+            _ => false,
+        },
+        _ => {
+            let limit = text.find('/').unwrap_or(text.len());
+            let shortest = if *part == Part::Any { 0 } else { 1 };
+            for end in (shortest..=limit).filter(|end| text.is_char_boundary(*end)) {
+                let saved = state.clone();
+                if accept(part, &text[..end], state) && match_parts(rest, &text[end..], state) {
+                    return true;
+                }
+                *state = saved;
+            }
+            false
+        }
+    }
+}
+
+// Si `value` sirve para el marcador, guardándolo en `state`
+fn accept(part: &Part, value: &str, state: &mut State) -> bool {
+    match part {
+        Part::Name => keep(&mut state.name, value.to_string()),
+        Part::Date(format) => parse_date(value, format).is_some_and(|date| keep(&mut state.date, date)),
+        Part::Ext => {
+            !value.starts_with('.')
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+                && keep(&mut state.ext, value.to_string())
+        }
+        _ => true,
+    }
+}
+
+fn keep<T: PartialEq>(slot: &mut Option<T>, value: T) -> bool {
+    match slot {
+        Some(previous) => *previous == value,
+        None => {
+            *slot = Some(value);
+            true
+        }
+    }
+}
+
+// Fecha escrita en el nombre de un backup, como produccion_04_28_2025 o prod_2025-04-28_17-48-40.
+// Si hay varias, vale la última
+pub fn date_in_name(name: &str) -> Option<NaiveDateTime> {
+    let separators = ['_', '-'];
+    let mut found = None;
+    for run in name.split(|c: char| !c.is_ascii_digit() && !separators.contains(&c)) {
+        // El tramo puede empezar con otra cosa, como el 2 de prod2_2025-04-28:
+        // se prueba desde el principio y desde cada separador
+        let starts = std::iter::once(0).chain(run.match_indices(separators).map(|(i, _)| i + 1));
+        let date = starts
+            .map(|start| run[start..].trim_end_matches(separators))
+            .find_map(|candidate| NAME_DATE_FORMATS.iter().find_map(|format| parse_date(candidate, format)));
+        if date.is_some() {
+            found = date;
+        }
+    }
+    found
+}
+
+// Si `text` coincide con un patrón con * y ?
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<Part> = pattern.chars()
+        .map(|c| match c {
+            '*' => Part::Any,
+            '?' => Part::One,
+            c => Part::Literal(c.to_string()),
+        })
+        .collect();
+    match_parts(&parts, text, &mut State::default())
+}
+
+// Fecha con hora o, si el formato no la tiene, a medianoche
+fn parse_date(value: &str, format: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, format).ok()
+        .or_else(|| NaiveDate::parse_from_str(value, format).ok().map(|d| d.and_time(NaiveTime::MIN)))
+}
+
+// Escapar los caracteres especiales de los patrones de `find -path`
+fn glob_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d).map(|d| d.and_time(NaiveTime::MIN))
+    }
+
+    #[test]
+    fn templates_capture_name_date_and_extension() {
+        let t = PathTemplate::parse("{dir}/{name}/{date}/{name}.{ext}").unwrap();
+        let c = t.matches("/backups/", "/backups/prod/2025-04-28/prod.sql.gz").unwrap();
+        assert_eq!(c, Captures { name: "prod".into(), date: date(2025, 4, 28), ext: Some("sql.gz".into()) });
+        // {name} repetido debe coincidir
+        assert!(t.matches("/backups", "/backups/prod/2025-04-28/otra.sql").is_none());
+        assert!(t.matches("/backups", "/backups/prod/no-es-fecha/prod.sql").is_none());
+
+        let t = PathTemplate::parse("{dir}/{name}_{date:%Y%m%d}.{ext}").unwrap();
+        let c = t.matches("/backups", "/backups/mi_base_20250428.dump").unwrap();
+        assert_eq!((c.name.as_str(), c.date, c.ext.as_deref()), ("mi_base", date(2025, 4, 28), Some("dump")));
+
+        let t = PathTemplate::parse("{dir}/*/{name}.sql*").unwrap();
+        assert_eq!(t.matches("/b", "/b/2025/prod.sql").unwrap().name, "prod");
+        assert!(t.matches("/b", "/b/a/b/prod.sql").is_none());
+    }
+
+    #[test]
+    fn search_starts_at_the_deepest_fixed_directory() {
+        let t = PathTemplate::parse("{dir}/{name}/{date}/{name}.{ext}").unwrap();
+        assert_eq!(t.search("/backups", None).unwrap(), Search {
+            root: "/backups".into(),
+            depth: 3,
+            pattern: "/backups/*/*/*.*".into(),
+        });
+        assert_eq!(t.search("/backups", Some("prod")).unwrap(), Search {
+            root: "/backups/prod".into(),
+            depth: 2,
+            pattern: "/backups/prod/*/prod.*".into(),
+        });
+        let t = PathTemplate::parse("/srv/[x]/{name}.sql").unwrap();
+        assert_eq!(t.search("", Some("a")).unwrap().pattern, "/srv/\\[x]/a.sql");
+        assert!(PathTemplate::parse("{name}.sql").unwrap().search("relativo", None).is_err());
+    }
+
+    #[test]
+    fn invalid_templates_and_rendering() {
+        assert!(PathTemplate::parse("{dir}/dump.sql").is_err());
+        assert!(PathTemplate::parse("{dir}/{nombre}.sql").is_err());
+        assert!(PathTemplate::parse("{dir}/{name").is_err());
+        assert!(PathTemplate::parse("{dir}/{name}_{date:%Q}").is_err());
+        assert!(PathTemplate::parse("{dir}/{name}/{backup}").is_err());
+
+        let t = PathTemplate::parse("{dir}/{name}/{date}/{name}.{ext}").unwrap();
+        let when = date(2025, 4, 28).unwrap();
+        assert_eq!(t.render("/backups", "prod", when, "sql.gz").unwrap(), "/backups/prod/2025-04-28/prod.sql.gz");
+        assert!(t.render("/backups", "a/b", when, "sql").is_err());
+        assert!(PathTemplate::parse("{dir}/*/{name}.sql").unwrap().render("/b", "prod", when, "sql").is_err());
+    }
+
+    #[test]
+    fn dates_are_read_from_backup_names() {
+        assert_eq!(date_in_name("produccion_04_28_2025"), date(2025, 4, 28));
+        assert_eq!(date_in_name("prod_2025-04-28"), date(2025, 4, 28));
+        assert_eq!(date_in_name("prod2_20250428"), date(2025, 4, 28));
+        let stamp = date_in_name("prod_2025-04-28_17-48-40.zip").unwrap();
+        assert_eq!(stamp.format("%Y-%m-%d %H:%M:%S").to_string(), "2025-04-28 17:48:40");
+        assert_eq!(date_in_name("odoo_16"), None);
+        assert_eq!(date_in_name("prod_13_45_2025"), None);
+
+        assert!(glob_match("prod*_2025", "produccion_04_28_2025"));
+        assert!(glob_match("prod_??", "prod_eu"));
+        assert!(!glob_match("prod_?", "prod_eu"));
+    }
+
+    #[test]
+    fn target_names_come_from_the_backup_and_the_restore_date() {
+        let now = date(2025, 4, 28).unwrap();
+        let t = TargetTemplate::parse(DEFAULT_TARGET_TEMPLATE).unwrap();
+        assert_eq!(t.render("produccion_04_28_2025", now), "produccion_04_28_2025_20250428");
+        let t = TargetTemplate::parse("staging_{backup}_{date:%d%m}").unwrap();
+        assert_eq!(t.render("prod", now), "staging_prod_2804");
+        assert!(TargetTemplate::parse("{name}_copia").is_err());
+        assert!(TargetTemplate::parse("{backup}_*").is_err());
+
+        // Con plantilla de ruta, la ruta de un dump da su {name}
+        let p = Profile {
+            dir_backup: Some("/b".into()),
+            path_template: Some("{dir}/{name}/{date}.{ext}".into()),
+            target_template: Some("{backup}_copia".into()),
+            ..Profile::default()
+        };
+        assert_eq!(p.default_target("/b/prod/2025-04-28.sql").unwrap(), "prod_copia");
+        assert_eq!(p.default_target("prod").unwrap(), "prod_copia");
+    }
+}